@@ -0,0 +1,36 @@
+#version 330
+
+uniform sampler2D distance_field;
+uniform bool effects_pass; // glow and outline first, for every glyph, so no glyph's effects cover another's fill
+uniform bool clip_to_playfield; // screen space text reaches into the bars beside or above it
+
+in vec2 v_texture_coordinates;
+in vec2 v_position;
+in vec4 v_tint;
+in float v_outline_width;
+in vec4 v_outline_color;
+in float v_glow_radius;
+in vec4 v_glow_color;
+
+out vec4 color;
+
+void main () {
+  if (clip_to_playfield && (v_position.x > 1.0 || v_position.x < -1.0 || v_position.y > 1.0 || v_position.y < -1.0)) discard;
+
+  // 0.5 on the glyph's edge, rising inside it and falling outside
+  float distance = texture(distance_field, v_texture_coordinates).r;
+  float smoothing = max(fwidth(distance) * 0.5, 0.001); // about half a pixel, for antialiasing at any scale
+
+  if (effects_pass) {
+    float outline_edge = 0.5 - v_outline_width;
+    float outline = smoothstep(outline_edge - smoothing, outline_edge + smoothing, distance);
+    float glow = v_glow_radius > 0.0 ? smoothstep(outline_edge - v_glow_radius, outline_edge, distance) : 0.0;
+    color = vec4(v_glow_color.rgb, v_glow_color.a * glow);
+    color = mix(color, v_outline_color, outline);
+  }
+  else {
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    color = vec4(v_tint.rgb, v_tint.a * fill);
+  }
+  if (color.a == 0.0) discard;
+}
@@ -0,0 +1,38 @@
+#version 330
+
+uniform mat4 view; // world to view space, the camera, identity for screen space text
+uniform mat4 projection; // view space to the window, fitting the playfield square in the middle
+
+// per vertex, a corner of the unit quad
+in vec2 corner;
+
+// per instance, one glyph
+in vec2 center;
+in vec2 half_size;
+in float depth;
+in vec4 texture_region; // left, top, right, bottom
+in vec4 tint;
+in float outline_width; // in distance field units, where 0.5 is the glyph's edge
+in vec4 outline_color;
+in float glow_radius; // past the outline, in distance field units too
+in vec4 glow_color;
+
+out vec2 v_texture_coordinates;
+out vec2 v_position; // view space vertex position (x & y), the playfield square is -1..1
+out vec4 v_tint;
+out float v_outline_width;
+out vec4 v_outline_color;
+out float v_glow_radius;
+out vec4 v_glow_color;
+
+void main () {
+  vec2 world = center + corner * half_size;
+  v_texture_coordinates = mix(texture_region.xy, texture_region.zw, vec2(corner.x, -corner.y) * 0.5 + 0.5);
+  v_tint = tint;
+  v_outline_width = outline_width;
+  v_outline_color = outline_color;
+  v_glow_radius = glow_radius;
+  v_glow_color = glow_color;
+  v_position = (view * vec4(world, 0.0, 1.0)).xy;
+  gl_Position = projection * vec4(v_position, depth, 1.0);
+}
@@ -1,14 +1,16 @@
 #version 330
 
 uniform sampler2D texture_atlas;
+uniform bool clip_to_playfield; // screen space text reaches into the bars beside or above it
 
 in vec2 v_texture_coordinates;
 in vec2 v_position;
+in vec4 v_tint;
 
 out vec4 color;
 
 void main () {
-  if (v_position.x > 1.0 || v_position.x < -1.0 || v_position.y > 1.0 || v_position.y < -1.0) discard;
-  color = texture(texture_atlas, v_texture_coordinates);
+  if (clip_to_playfield && (v_position.x > 1.0 || v_position.x < -1.0 || v_position.y > 1.0 || v_position.y < -1.0)) discard;
+  color = texture(texture_atlas, v_texture_coordinates) * v_tint;
   if (color.a == 0.0) discard;
 }
@@ -0,0 +1,29 @@
+#version 330
+
+uniform sampler2D frame;
+uniform sampler2D bloom_mask; // whatever glows in its own color, everything else black
+uniform vec2 frame_size; // in pixels
+uniform float radius; // how far the glow spreads, in pixels
+uniform float intensity;
+
+in vec2 v_texture_coordinates;
+
+out vec4 color;
+
+const int TAPS = 6; // samples on either side of the pixel, along both axes
+
+void main () {
+  vec3 glow = vec3(0.0);
+  float total = 0.0;
+  for (int x = -TAPS; x <= TAPS; x++) {
+    for (int y = -TAPS; y <= TAPS; y++) {
+      vec2 offset = vec2(x, y) / float(TAPS);
+      float weight = exp(-2.0 * dot(offset, offset)); // gaussian, close to nothing by the corners
+      glow += texture(bloom_mask, v_texture_coordinates + offset * radius / frame_size).rgb * weight;
+      total += weight;
+    }
+  }
+  glow *= intensity / total;
+  vec4 scene = texture(frame, v_texture_coordinates);
+  color = vec4(scene.rgb + glow, max(scene.a, max(glow.r, max(glow.g, glow.b))));
+}
@@ -0,0 +1,26 @@
+#version 330
+
+uniform sampler2D frame;
+uniform vec2 frame_size; // in pixels
+uniform float curvature; // how much the screen bulges, 0.0 for flat
+uniform float scanline_strength; // how dark the gaps between scanlines get
+uniform float scanline_height; // in pixels
+
+in vec2 v_texture_coordinates;
+
+out vec4 color;
+
+void main () {
+  // bulge out from the middle, further along the diagonals
+  vec2 centered = v_texture_coordinates * 2.0 - 1.0;
+  centered *= 1.0 + curvature * dot(centered, centered);
+  vec2 bulged = centered * 0.5 + 0.5;
+  if (any(lessThan(bulged, vec2(0.0))) || any(greaterThan(bulged, vec2(1.0)))) {
+    color = vec4(0.0, 0.0, 0.0, 1.0); // past the edge of the tube
+    return;
+  }
+
+  vec4 scene = texture(frame, bulged);
+  float line = sin(bulged.y * frame_size.y / scanline_height * 3.14159265);
+  color = vec4(scene.rgb * mix(1.0 - scanline_strength, 1.0, line * line), scene.a);
+}
@@ -0,0 +1,28 @@
+#version 330
+
+uniform sampler2D frame;
+uniform sampler2D lut; // 16 slices of 16x16 side by side, red goes across a slice, green down, blue picks the slice
+uniform float strength; // 0.0 leaves colors alone, 1.0 is the full grade
+
+in vec2 v_texture_coordinates;
+
+out vec4 color;
+
+const float SIZE = 16.0;
+
+// the table's made for srgb colors, blending between the two closest slices
+vec3 look_up(vec3 srgb) {
+  float slice = srgb.b * (SIZE - 1.0);
+  float lower = floor(slice);
+  float upper = min(lower + 1.0, SIZE - 1.0);
+  vec2 within = (srgb.rg * (SIZE - 1.0) + 0.5) / vec2(SIZE * SIZE, SIZE);
+  vec3 a = texture(lut, within + vec2(lower / SIZE, 0.0)).rgb;
+  vec3 b = texture(lut, within + vec2(upper / SIZE, 0.0)).rgb;
+  return mix(a, b, slice - lower);
+}
+
+void main () {
+  vec4 scene = texture(frame, v_texture_coordinates);
+  vec3 graded = pow(look_up(pow(clamp(scene.rgb, 0.0, 1.0), vec3(1.0 / 2.2))), vec3(2.2));
+  color = vec4(mix(scene.rgb, graded, strength), scene.a);
+}
@@ -0,0 +1,11 @@
+#version 330
+
+// per vertex, a corner of the unit quad, which covers the whole frame
+in vec2 corner;
+
+out vec2 v_texture_coordinates;
+
+void main () {
+  v_texture_coordinates = corner * 0.5 + 0.5;
+  gl_Position = vec4(corner, 0.0, 1.0);
+}
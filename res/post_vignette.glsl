@@ -0,0 +1,19 @@
+#version 330
+
+uniform sampler2D frame;
+uniform vec2 frame_size; // in pixels
+uniform float strength; // how dark the corners get
+uniform float radius; // where darkening starts, in frame heights from the middle
+uniform float softness; // how far it takes to get fully dark
+
+in vec2 v_texture_coordinates;
+
+out vec4 color;
+
+void main () {
+  vec4 scene = texture(frame, v_texture_coordinates);
+  // round whatever shape the window is
+  vec2 centered = (v_texture_coordinates - 0.5) * vec2(frame_size.x / frame_size.y, 1.0);
+  float lit = smoothstep(radius + softness, radius, length(centered));
+  color = vec4(scene.rgb * mix(1.0 - strength, 1.0, lit), scene.a);
+}
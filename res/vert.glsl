@@ -1,20 +1,30 @@
 #version 330
 
-uniform float window_aspect_ratio;
+uniform mat4 view; // world to view space, the camera, identity for screen space text
+uniform mat4 projection; // view space to the window, fitting the playfield square in the middle
+
+// per vertex, a corner of the unit quad
+in vec2 corner;
+
+// per instance, one sprite
+in vec2 center;
+in vec2 half_size;
+in float rotation; // radians, counter-clockwise
+in float depth;
+in vec4 texture_region; // left, top, right, bottom
+in vec4 tint;
 
-in vec3 position;
-in vec2 texture_coordinates;
 out vec2 v_texture_coordinates;
-out vec2 v_position; // untransformed vertex position (x & y)
+out vec2 v_position; // view space vertex position (x & y), the playfield square is -1..1
+out vec4 v_tint;
 
 void main () {
-  v_position = position.xy;
-  v_texture_coordinates = texture_coordinates;
-  gl_Position = vec4(position, 1.0);
-  if (window_aspect_ratio > 1) {
-    gl_Position.x /= window_aspect_ratio;
-  }
-  else {
-    gl_Position.y *= window_aspect_ratio;
-  }
+  vec2 local = corner * half_size;
+  float s = sin(rotation);
+  float c = cos(rotation);
+  vec2 world = center + vec2(local.x * c - local.y * s, local.x * s + local.y * c);
+  v_texture_coordinates = mix(texture_region.xy, texture_region.zw, vec2(corner.x, -corner.y) * 0.5 + 0.5);
+  v_tint = tint;
+  v_position = (view * vec4(world, 0.0, 1.0)).xy;
+  gl_Position = projection * vec4(v_position, depth, 1.0);
 }
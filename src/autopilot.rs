@@ -0,0 +1,180 @@
+use super::logic;
+use super::GameState;
+
+use std::time::{Duration, Instant};
+
+use glium::glutin::event::VirtualKeyCode;
+use serde::Deserialize;
+
+#[cfg(test)]
+mod tests;
+
+pub const DECISION_INTERVAL: Duration = Duration::from_millis(50); // how often the autopilot reconsiders what to do
+pub const HOLD_TIME: Duration = Duration::from_millis(150); // how long each simulated action is held for
+pub const TICK_TIME: Duration = Duration::from_millis(30); // length of a simulated tick
+pub const SEARCH_DEPTH: u32 = 3; // number of consecutive actions to look ahead
+
+const DEATH_VALUE: f32 = -1_000_000.0;
+const SCORE_WEIGHT: f32 = 100.0;
+const COIN_WEIGHT: f32 = 5.0;
+const ROCK_WEIGHT: f32 = 50.0;
+const CENTER_WEIGHT: f32 = 10.0;
+const ROCK_CAUTION_DISTANCE: f32 = 0.5; // rocks further away than this aren't worth worrying about yet
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Idle,
+    Flap,
+    Left,
+    Right,
+    FlapLeft,
+    FlapRight,
+}
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Idle,
+        Action::Flap,
+        Action::Left,
+        Action::Right,
+        Action::FlapLeft,
+        Action::FlapRight,
+    ];
+
+    // keys held down to perform this action
+    pub fn keys(self) -> Vec<VirtualKeyCode> {
+        match self {
+            Action::Idle => Vec::new(),
+            Action::Flap => vec![VirtualKeyCode::Space],
+            Action::Left => vec![VirtualKeyCode::Left],
+            Action::Right => vec![VirtualKeyCode::Right],
+            Action::FlapLeft => vec![VirtualKeyCode::Space, VirtualKeyCode::Left],
+            Action::FlapRight => vec![VirtualKeyCode::Space, VirtualKeyCode::Right],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Autopilot {
+    action: Action,
+    last_decision_time: Option<Instant>,
+}
+impl Autopilot {
+    pub fn new() -> Self {
+        Autopilot {
+            action: Action::Idle,
+            last_decision_time: None,
+        }
+    }
+
+    // pick an action, sticking with the previous one until DECISION_INTERVAL has passed
+    pub fn update(&mut self, game_state: &GameState, now: Instant) -> Action {
+        if match self.last_decision_time {
+            None => Duration::MAX,
+            Some(time) => now.duration_since(time),
+        } >= DECISION_INTERVAL
+        {
+            self.last_decision_time = Some(now);
+            self.action = choose_action(game_state, now);
+        }
+        self.action
+    }
+}
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// hold an action for HOLD_TIME, returns the resulting state and its timestamp
+fn simulate(mut game_state: GameState, mut now: Instant, action: Action) -> (GameState, Instant) {
+    game_state.keys = action.keys();
+    let mut elapsed = Duration::ZERO;
+    while elapsed < HOLD_TIME && !game_state.dead {
+        now += TICK_TIME;
+        elapsed += TICK_TIME;
+        logic::tick(&mut game_state, now, TICK_TIME.as_secs_f32());
+    }
+    (game_state, now)
+}
+
+// heuristic value of a game state, higher is better
+fn evaluate(game_state: &GameState) -> f32 {
+    let birdy = game_state.birdy;
+    let mut value = game_state.score as f32 * SCORE_WEIGHT;
+
+    // stay out from under (or over) approaching rocks
+    for rock in game_state.rocks.iter() {
+        let approaching = (rock.y - birdy.y).signum() != rock.y_velocity.signum();
+        if approaching {
+            // negative when the hitboxes line up on that axis
+            let x_gap = (rock.x - birdy.x).abs() - rock.width - birdy.width;
+            let y_gap = (rock.y - birdy.y).abs() - rock.height - birdy.height;
+            value -= ROCK_WEIGHT
+                * f32::max(1.0 - x_gap / ROCK_CAUTION_DISTANCE, 0.0)
+                * f32::max(1.0 - y_gap / ROCK_CAUTION_DISTANCE, 0.0);
+        }
+    }
+
+    // drift towards the nearest coin
+    if let Some(distance) = game_state
+        .coins
+        .iter()
+        .map(|coin| f32::hypot(coin.x - birdy.x, coin.y - birdy.y))
+        .reduce(f32::min)
+    {
+        value -= distance * COIN_WEIGHT;
+    }
+
+    // keep away from the floor and ceiling
+    value -= birdy.y.abs() * CENTER_WEIGHT;
+
+    value
+}
+
+fn search(game_state: &GameState, now: Instant, depth: u32) -> f32 {
+    if game_state.dead {
+        // dying later is better than dying sooner
+        return DEATH_VALUE * (depth + 1) as f32;
+    }
+    if depth == 0 {
+        return evaluate(game_state);
+    }
+    Action::ALL
+        .iter()
+        .map(|&action| {
+            let (next_state, next_now) = simulate(game_state.clone(), now, action);
+            search(&next_state, next_now, depth - 1)
+        })
+        .fold(f32::MIN, f32::max)
+}
+
+// best action to take right now, found by simulating every combination of actions SEARCH_DEPTH deep
+pub fn choose_action(game_state: &GameState, now: Instant) -> Action {
+    let mut best_action = Action::Idle;
+    let mut best_value = f32::MIN;
+    for action in Action::ALL {
+        let (next_state, next_now) = simulate(game_state.clone(), now, action);
+        let value = search(&next_state, next_now, SEARCH_DEPTH - 1);
+        if value > best_value {
+            best_value = value;
+            best_action = action;
+        }
+    }
+    best_action
+}
+
+// let the autopilot play for a while, returns whether it's still alive at the end
+pub fn survives(mut game_state: GameState, mut now: Instant, duration: Duration) -> bool {
+    let mut autopilot = Autopilot::new();
+    let end = now + duration;
+    while now < end {
+        game_state.keys = autopilot.update(&game_state, now).keys();
+        now += TICK_TIME;
+        logic::tick(&mut game_state, now, TICK_TIME.as_secs_f32());
+        if game_state.dead {
+            return false;
+        }
+    }
+    true
+}
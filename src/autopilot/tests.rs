@@ -0,0 +1,29 @@
+use super::*;
+
+#[test]
+fn action_keys() {
+    assert!(Action::Idle.keys().is_empty());
+    assert_eq!(
+        vec![VirtualKeyCode::Space, VirtualKeyCode::Right],
+        Action::FlapRight.keys()
+    );
+}
+
+#[test]
+fn dodges_falling_rock() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    // no more spawns, just the one rock
+    game_state.last_rock_spawn_time = Some(now + Duration::from_secs(3600));
+    game_state.last_coin_spawn_time = Some(now + Duration::from_secs(3600));
+    game_state.rocks.push(logic::PhysObj {
+        x: game_state.birdy.x,
+        y: 0.8,
+        x_velocity: 0.0,
+        y_velocity: -logic::rock::MAX_VELOCITY,
+        width: logic::rock::MAX_SIZE,
+        height: logic::rock::MAX_SIZE,
+    });
+
+    assert!(survives(game_state, now, Duration::from_secs(5)));
+}
@@ -0,0 +1,46 @@
+use flap::pack;
+use flap::render::sprite_atlas::SpriteAtlas;
+
+const USAGE: &str =
+    "usage: flap-pack <sprite folder> <atlas.png> <atlas.json> [--padding <pixels>] [--extrude <pixels>]";
+
+// pack a folder of individual sprite pngs into an atlas image and its sprite metadata
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut padding = pack::DEFAULT_PADDING;
+    let mut extrusion = pack::DEFAULT_EXTRUSION;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--padding" => padding = args.next().ok_or(USAGE)?.parse()?,
+            "--extrude" => extrusion = args.next().ok_or(USAGE)?.parse()?,
+            _ => paths.push(arg),
+        }
+    }
+    let [sprite_dir, atlas_path, metadata_path] = paths.as_slice() else {
+        return Err(USAGE.into());
+    };
+
+    let sprites = pack::load_sprites(sprite_dir)?;
+    let (atlas, mut sprite_atlas) = pack::pack(&sprites, padding, extrusion)?;
+    // animations are written by hand, keep the ones already in the metadata we're replacing
+    if let Ok(json) = std::fs::read_to_string(metadata_path) {
+        if let Ok(old) = serde_json::from_str::<SpriteAtlas>(&json) {
+            sprite_atlas.animations = old.animations;
+        }
+    }
+    atlas.save_png(atlas_path)?;
+    // by way of a json value, which keeps its keys sorted, so repacking the same sprites gives the same file
+    let metadata = serde_json::to_value(&sprite_atlas)?;
+    std::fs::write(
+        metadata_path,
+        serde_json::to_string_pretty(&metadata)? + "\n",
+    )?;
+    println!(
+        "packed {} sprites into a {}x{} atlas",
+        sprites.len(),
+        atlas.width,
+        atlas.height
+    );
+    Ok(())
+}
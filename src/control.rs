@@ -0,0 +1,218 @@
+use super::autopilot::Action;
+use super::logic::PhysObj;
+use super::GameState;
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+pub const STEP_TIME: Duration = Duration::from_nanos(16_666_667); // length of a tick requested through the step command
+pub const MAX_TICKS_PER_FRAME: u32 = 600; // stepped ticks run per frame, the rest wait so a huge step can't freeze the game
+pub const MAX_LINE_LENGTH: usize = 64 * 1024; // clients sending longer requests get disconnected
+pub const MAX_QUEUED_RESPONSE_BYTES: usize = 1024 * 1024; // clients that stop reading get disconnected once this much is waiting
+
+// one request per line, e.g. {"cmd": "step", "ticks": 10}
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    Action { action: Option<Action> }, // null hands control back to the keyboard
+    Pause,
+    Resume,
+    Step { ticks: u32 },
+    State,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StateSnapshot {
+    pub birdy: PhysObj,
+    pub rocks: Vec<PhysObj>,
+    pub coins: Vec<PhysObj>,
+    pub score: u32,
+    pub combo: u32,
+    pub dead: bool,
+    pub paused: bool,
+}
+
+// one response per request, e.g. {"ok": true} or {"ok": false, "error": "..."}
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateSnapshot>,
+}
+impl Response {
+    fn ok() -> Self {
+        Response {
+            ok: true,
+            error: None,
+            state: None,
+        }
+    }
+
+    fn error(error: String) -> Self {
+        Response {
+            ok: false,
+            error: Some(error),
+            state: None,
+        }
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    buffer: Vec<u8>,    // bytes read that don't make up a whole line yet
+    responses: Vec<u8>, // bytes of responses the client hasn't taken yet
+    hung_up: bool,      // done sending, kept around until it's been sent every response
+}
+
+// localhost server for driving the game from scripts, speaks line-delimited JSON
+pub struct ControlServer {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    action: Option<Action>,
+    paused: bool,
+    pending_ticks: u32,
+}
+impl ControlServer {
+    pub fn bind(addr: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(ControlServer {
+            listener,
+            clients: Vec::new(),
+            action: None,
+            paused: false,
+            pending_ticks: 0,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // action injected by a client, overriding the keyboard
+    pub fn action(&self) -> Option<Action> {
+        self.action
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    // ticks requested by step commands since the last call, at most MAX_TICKS_PER_FRAME, the rest are kept for later calls
+    pub fn take_pending_ticks(&mut self) -> u32 {
+        let ticks = u32::min(self.pending_ticks, MAX_TICKS_PER_FRAME);
+        self.pending_ticks -= ticks;
+        ticks
+    }
+
+    // accept new clients and answer everything they've sent, never blocks
+    pub fn poll(&mut self, game_state: &GameState) {
+        // stops at WouldBlock, or anything else we couldn't do much about anyway
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client {
+                    stream,
+                    buffer: Vec::new(),
+                    responses: Vec::new(),
+                    hung_up: false,
+                });
+            }
+        }
+
+        let mut clients = std::mem::take(&mut self.clients);
+        clients.retain_mut(|client| self.serve(client, game_state));
+        self.clients = clients;
+    }
+
+    // returns whether the client is still connected
+    fn serve(&mut self, client: &mut Client, game_state: &GameState) -> bool {
+        let mut chunk = [0; 1024];
+        while !client.hung_up {
+            match client.stream.read(&mut chunk) {
+                Ok(0) => client.hung_up = true,
+                Ok(count) => {
+                    client.buffer.extend_from_slice(&chunk[..count]);
+                    // whatever's left gets read next time, once the lines so far are answered
+                    if client.buffer.len() > MAX_LINE_LENGTH {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => return false,
+            }
+        }
+
+        while let Some(end) = client.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = client.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str(&line) {
+                Ok(command) => self.handle(command, game_state),
+                Err(e) => Response::error(e.to_string()),
+            };
+            serde_json::to_writer(&mut client.responses, &response).unwrap();
+            client.responses.push(b'\n');
+        }
+        if client.buffer.len() > MAX_LINE_LENGTH {
+            return false;
+        }
+
+        // send as much as the client will take without blocking, the rest waits for the next poll
+        while !client.responses.is_empty() {
+            match client.stream.write(&client.responses) {
+                Ok(0) => return false,
+                Ok(count) => {
+                    client.responses.drain(..count);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => return false,
+            }
+        }
+        if client.responses.len() > MAX_QUEUED_RESPONSE_BYTES {
+            return false;
+        }
+
+        !(client.hung_up && client.responses.is_empty())
+    }
+
+    pub fn handle(&mut self, command: Command, game_state: &GameState) -> Response {
+        match command {
+            Command::Action { action } => self.action = action,
+            Command::Pause => self.paused = true,
+            Command::Resume => self.paused = false,
+            Command::Step { ticks } => {
+                if !self.paused {
+                    return Response::error("can only step while paused".to_string());
+                }
+                self.pending_ticks = self.pending_ticks.saturating_add(ticks);
+            }
+            Command::State => {
+                return Response {
+                    state: Some(StateSnapshot {
+                        birdy: game_state.birdy,
+                        rocks: game_state.rocks.clone(),
+                        coins: game_state.coins.clone(),
+                        score: game_state.score,
+                        combo: game_state.combo,
+                        dead: game_state.dead,
+                        paused: self.paused,
+                    }),
+                    ..Response::ok()
+                }
+            }
+        }
+        Response::ok()
+    }
+}
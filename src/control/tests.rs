@@ -0,0 +1,141 @@
+use super::*;
+
+use std::io::{BufRead, BufReader};
+
+fn request(server: &mut ControlServer, game_state: &GameState, line: &str) -> serde_json::Value {
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    writeln!(stream, "{}", line).unwrap();
+
+    let mut reader = BufReader::new(stream);
+    let mut response = String::new();
+    for _ in 0..500 {
+        server.poll(game_state);
+        if reader.read_line(&mut response).is_ok() && response.ends_with('\n') {
+            return serde_json::from_str(&response).unwrap();
+        }
+    }
+    panic!("no response to {}", line);
+}
+
+#[test]
+fn parse_commands() {
+    assert_eq!(
+        Command::Step { ticks: 3 },
+        serde_json::from_str(r#"{"cmd": "step", "ticks": 3}"#).unwrap()
+    );
+    assert_eq!(
+        Command::Action {
+            action: Some(Action::FlapLeft)
+        },
+        serde_json::from_str(r#"{"cmd": "action", "action": "flap_left"}"#).unwrap()
+    );
+    assert_eq!(
+        Command::Action { action: None },
+        serde_json::from_str(r#"{"cmd": "action", "action": null}"#).unwrap()
+    );
+}
+
+#[test]
+fn pause_and_step() {
+    let game_state = GameState::new();
+    let mut server = ControlServer::bind("127.0.0.1:0").unwrap();
+
+    let response = request(&mut server, &game_state, r#"{"cmd": "step", "ticks": 5}"#);
+    assert_eq!(serde_json::Value::Bool(false), response["ok"]);
+
+    request(&mut server, &game_state, r#"{"cmd": "pause"}"#);
+    request(&mut server, &game_state, r#"{"cmd": "step", "ticks": 5}"#);
+    request(&mut server, &game_state, r#"{"cmd": "step", "ticks": 2}"#);
+    assert!(server.paused());
+    assert_eq!(7, server.take_pending_ticks());
+    assert_eq!(0, server.take_pending_ticks());
+}
+
+#[test]
+fn query_state() {
+    let mut game_state = GameState::new();
+    game_state.score = 12;
+    let mut server = ControlServer::bind("127.0.0.1:0").unwrap();
+
+    let response = request(&mut server, &game_state, r#"{"cmd": "state"}"#);
+    assert_eq!(serde_json::Value::Bool(true), response["ok"]);
+    assert_eq!(12, response["state"]["score"]);
+    assert_eq!(0.0, response["state"]["birdy"]["x"]);
+
+    let response = request(&mut server, &game_state, "not json");
+    assert_eq!(serde_json::Value::Bool(false), response["ok"]);
+}
+
+#[test]
+fn huge_steps() {
+    let game_state = GameState::new();
+    let mut server = ControlServer::bind("127.0.0.1:0").unwrap();
+    server.handle(Command::Pause, &game_state);
+    server.handle(Command::Step { ticks: u32::MAX }, &game_state);
+    server.handle(Command::Step { ticks: u32::MAX }, &game_state);
+
+    // they saturate instead of overflowing, and come out a frame's worth at a time
+    assert_eq!(MAX_TICKS_PER_FRAME, server.take_pending_ticks());
+    assert_eq!(u32::MAX - MAX_TICKS_PER_FRAME, server.pending_ticks);
+}
+
+#[test]
+fn overlong_lines() {
+    let game_state = GameState::new();
+    let mut server = ControlServer::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    stream.write_all(&[b' '; MAX_LINE_LENGTH + 1]).unwrap();
+
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+
+    // the server hangs up without answering
+    let mut byte = [0];
+    for _ in 0..500 {
+        server.poll(&game_state);
+        match stream.read(&mut byte) {
+            Ok(0) => return,
+            Err(e) if e.kind() == ErrorKind::ConnectionReset => return,
+            Ok(_) => panic!("got a response to an overlong line"),
+            Err(_) => (),
+        }
+    }
+    panic!("still connected after an overlong line");
+}
+
+#[test]
+fn answers_after_hanging_up() {
+    let game_state = GameState::new();
+    let mut server = ControlServer::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    writeln!(stream, r#"{{"cmd": "pause"}}"#).unwrap();
+    writeln!(stream, r#"{{"cmd": "step", "ticks": 3}}"#).unwrap();
+    stream.shutdown(std::net::Shutdown::Write).unwrap();
+
+    // every reply comes through before the server hangs up too
+    let mut reader = BufReader::new(stream);
+    let mut responses = String::new();
+    for _ in 0..500 {
+        server.poll(&game_state);
+        if let Ok(0) = reader.read_line(&mut responses) {
+            break;
+        }
+    }
+    let responses: Vec<serde_json::Value> = responses
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(2, responses.len());
+    assert!(responses
+        .iter()
+        .all(|response| response["ok"] == serde_json::Value::Bool(true)));
+    assert!(server.clients.is_empty());
+    assert_eq!(3, server.take_pending_ticks());
+}
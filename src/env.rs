@@ -0,0 +1,174 @@
+use super::autopilot::Action;
+use super::logic;
+use super::GameState;
+
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+pub const NEARBY_ROCKS: usize = 4; // how many of the closest rocks show up in a feature observation
+pub const NEARBY_COINS: usize = 2;
+pub const FEATURE_COUNT: usize = 4 + 4 * (NEARBY_ROCKS + NEARBY_COINS);
+
+pub const SURVIVAL_REWARD: f32 = 0.01; // reward for every step the birdy stays alive
+pub const DEATH_REWARD: f32 = -10.0;
+
+const BIRDY_PIXEL: u8 = 255;
+const COIN_PIXEL: u8 = 170;
+const ROCK_PIXEL: u8 = 85;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ObservationKind {
+    Features, // birdy, nearby rocks and nearby coins, see FEATURE_COUNT
+    Pixels { width: usize, height: usize }, // greyscale image of the playfield
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Observation {
+    Features(Vec<f32>),
+    Pixels {
+        width: usize,
+        height: usize,
+        data: Vec<u8>, // row-major, top row first
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EnvConfig {
+    pub observation: ObservationKind,
+    pub tick_time: Duration, // fixed length of a single logic tick
+    pub ticks_per_step: u32, // how many ticks an action is held for
+}
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            observation: ObservationKind::Features,
+            tick_time: Duration::from_nanos(16_666_667),
+            ticks_per_step: 4,
+        }
+    }
+}
+
+// gym-style wrapper around the game, runs as fast as the CPU allows without a window
+pub struct Env {
+    config: EnvConfig,
+    game_state: GameState,
+    now: Instant, // simulated time, advanced by tick_time every tick
+}
+impl Env {
+    pub fn new(config: EnvConfig) -> Self {
+        Env {
+            config,
+            game_state: GameState::new(),
+            now: Instant::now(),
+        }
+    }
+
+    pub fn config(&self) -> EnvConfig {
+        self.config
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game_state = GameState::with_seed(seed);
+        self.observe()
+    }
+
+    // hold an action for ticks_per_step ticks, returns (observation, reward, done)
+    // once an episode is done, stepping does nothing until the next reset
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.game_state.dead {
+            return (self.observe(), 0.0, true);
+        }
+        let last_score = self.game_state.score;
+        self.game_state.keys = action.keys();
+        for _ in 0..self.config.ticks_per_step {
+            self.now += self.config.tick_time;
+            logic::tick(
+                &mut self.game_state,
+                self.now,
+                self.config.tick_time.as_secs_f32(),
+            );
+            if self.game_state.dead {
+                break;
+            }
+        }
+
+        let reward = if self.game_state.dead {
+            DEATH_REWARD
+        } else {
+            (self.game_state.score - last_score) as f32 + SURVIVAL_REWARD
+        };
+        (self.observe(), reward, self.game_state.dead)
+    }
+
+    pub fn observe(&self) -> Observation {
+        match self.config.observation {
+            ObservationKind::Features => Observation::Features(features(&self.game_state)),
+            ObservationKind::Pixels { width, height } => Observation::Pixels {
+                width,
+                height,
+                data: pixels(&self.game_state, width, height),
+            },
+        }
+    }
+}
+
+// position and velocity of the birdy, followed by the closest rocks and coins relative to it
+pub fn features(game_state: &GameState) -> Vec<f32> {
+    let birdy = game_state.birdy;
+    let mut features = vec![birdy.x, birdy.y, birdy.x_velocity, birdy.y_velocity];
+
+    for (objs, count) in [
+        (&game_state.rocks, NEARBY_ROCKS),
+        (&game_state.coins, NEARBY_COINS),
+    ] {
+        let mut nearby: Vec<logic::PhysObj> = objs.clone();
+        nearby.sort_by(|a, b| {
+            let a = f32::hypot(a.x - birdy.x, a.y - birdy.y);
+            let b = f32::hypot(b.x - birdy.x, b.y - birdy.y);
+            a.total_cmp(&b)
+        });
+        for i in 0..count {
+            match nearby.get(i) {
+                // missing objects are all zeros, including their size
+                None => features.extend_from_slice(&[0.0; 4]),
+                Some(obj) => features.extend_from_slice(&[
+                    obj.x - birdy.x,
+                    obj.y - birdy.y,
+                    obj.y_velocity,
+                    obj.width,
+                ]),
+            }
+        }
+    }
+
+    features
+}
+
+// greyscale picture of the playfield, one byte per pixel
+pub fn pixels(game_state: &GameState, width: usize, height: usize) -> Vec<u8> {
+    let mut data = vec![0; width * height];
+    let mut fill = |obj: logic::PhysObj, value: u8| {
+        // playfield runs from -1.0 to 1.0 on both axes, y pointing up
+        let to_column = |x: f32| ((x + 1.0) / 2.0 * width as f32).clamp(0.0, width as f32) as usize;
+        let to_row = |y: f32| ((1.0 - y) / 2.0 * height as f32).clamp(0.0, height as f32) as usize;
+        for row in to_row(obj.y + obj.height)..to_row(obj.y - obj.height) {
+            for column in to_column(obj.x - obj.width)..to_column(obj.x + obj.width) {
+                data[column + row * width] = value;
+            }
+        }
+    };
+    for rock in game_state.rocks.iter() {
+        fill(*rock, ROCK_PIXEL);
+    }
+    for coin in game_state.coins.iter() {
+        fill(*coin, COIN_PIXEL);
+    }
+    fill(game_state.birdy, BIRDY_PIXEL);
+    data
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+#[test]
+fn same_seed_same_round() {
+    let mut a = Env::new(EnvConfig::default());
+    let mut b = Env::new(EnvConfig::default());
+    assert_eq!(a.reset(7), b.reset(7));
+    for action in [Action::Flap, Action::Left, Action::Idle, Action::FlapRight]
+        .into_iter()
+        .cycle()
+        .take(200)
+    {
+        assert_eq!(a.step(action), b.step(action));
+    }
+}
+
+#[test]
+fn feature_observation() {
+    let mut env = Env::new(EnvConfig::default());
+    match env.reset(0) {
+        Observation::Features(features) => {
+            assert_eq!(FEATURE_COUNT, features.len());
+            assert_eq!(&[0.0; 4], &features[..4]); // birdy starts centred and still
+        }
+        observation => panic!("expected features, got {:?}", observation),
+    }
+}
+
+#[test]
+fn pixel_observation() {
+    let mut env = Env::new(EnvConfig {
+        observation: ObservationKind::Pixels {
+            width: 40,
+            height: 40,
+        },
+        ..Default::default()
+    });
+    match env.reset(0) {
+        Observation::Pixels {
+            width,
+            height,
+            data,
+        } => {
+            assert_eq!((40, 40), (width, height));
+            assert_eq!(40 * 40, data.len());
+            assert_eq!(BIRDY_PIXEL, data[20 + 20 * 40]);
+            assert_eq!(0, data[0]);
+        }
+        observation => panic!("expected pixels, got {:?}", observation),
+    }
+}
+
+#[test]
+fn episode_ends_on_death() {
+    let mut env = Env::new(EnvConfig::default());
+    env.reset(0);
+    // nothing but a rock right where the birdy is
+    let birdy = env.game_state.birdy;
+    env.game_state.rocks.push(birdy);
+
+    let (_, reward, done) = env.step(Action::Idle);
+    assert!(done);
+    assert_eq!(DEATH_REWARD, reward);
+}
+
+#[test]
+fn stepping_after_done() {
+    let mut env = Env::new(EnvConfig::default());
+    env.reset(0);
+    let birdy = env.game_state.birdy;
+    env.game_state.rocks.push(birdy);
+    let (observation, _, done) = env.step(Action::Idle);
+    assert!(done);
+
+    // nothing moves and nothing's rewarded until the next reset
+    let now = env.now;
+    let (after, reward, done) = env.step(Action::FlapLeft);
+    assert!(done);
+    assert_eq!(0.0, reward);
+    assert_eq!(observation, after);
+    assert_eq!(now, env.now);
+
+    env.reset(0);
+    let (_, _, done) = env.step(Action::Idle);
+    assert!(!done);
+}
@@ -0,0 +1,74 @@
+pub mod autopilot;
+pub mod control;
+pub mod env;
+pub mod logic;
+pub mod pack;
+pub mod render;
+pub mod tui;
+
+use crate::logic::PhysObj;
+
+use std::time::Instant;
+
+use glium::glutin::event::VirtualKeyCode;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Clone)]
+pub struct GameState {
+    pub last_jump_time: Option<Instant>,
+    pub last_rock_spawn_time: Option<Instant>,
+    pub rock_fall_direction: f32,
+    pub next_rock: PhysObj,
+    pub last_coin_spawn_time: Option<Instant>,
+    pub next_coin: PhysObj,
+    pub birdy: PhysObj,
+    pub rocks: Vec<PhysObj>,
+    pub coins: Vec<PhysObj>,
+    pub score: u32,
+    pub combo: u32, // consecutive coins collected without the combo window running out
+    pub last_coin_time: Option<Instant>,
+    pub score_popups: Vec<logic::combo::ScorePopup>,
+    pub keys: Vec<VirtualKeyCode>,
+    pub dead: bool,
+    pub near_miss_margin: f32, // how close a rock has to pass the birdy to count as a near miss
+    pub events: Vec<logic::GameEvent>, // events emitted by the last tick
+    pub rng: StdRng, // all of the game's randomness comes from here, so a seed reproduces a whole round
+}
+impl GameState {
+    pub fn new() -> GameState {
+        GameState::from_rng(StdRng::from_entropy())
+    }
+
+    pub fn with_seed(seed: u64) -> GameState {
+        GameState::from_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn from_rng(mut rng: StdRng) -> GameState {
+        GameState {
+            last_jump_time: None,
+            last_rock_spawn_time: None,
+            rock_fall_direction: 1.0,
+            next_rock: logic::rock::new_rock(1.0, &mut rng),
+            last_coin_spawn_time: None,
+            next_coin: logic::coin::new_coin(&mut rng),
+            birdy: logic::birdy::new_birdy(),
+            rocks: Vec::new(),
+            coins: Vec::new(),
+            score: 0,
+            combo: 0,
+            last_coin_time: None,
+            score_popups: Vec::new(),
+            keys: Vec::new(),
+            dead: false,
+            near_miss_margin: 0.05,
+            events: Vec::new(),
+            rng,
+        }
+    }
+}
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
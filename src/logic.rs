@@ -1,17 +1,31 @@
 use super::GameState;
 use glium::glutin::event::VirtualKeyCode;
+use rand::Rng;
+use serde::Serialize;
 use std::time::{Duration, Instant};
 
+#[cfg(test)]
+mod tests;
+
 pub mod birdy;
 pub mod coin;
+pub mod combo;
 pub mod rock;
-pub mod pipe;
 
 pub const PLAYFIELD_BOUNCE_COEFFICIENT: f32 = -0.75; // portion of player's velocity to reflect when they collide with the bottom of the playfield.
 
 const DESPAWN_DISTANCE: f32 = 2.5;
 
-#[derive(Copy, Clone)]
+// things that happened during a tick, for anything outside of the game logic that wants to react to them
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Jumped,
+    CoinCollected { x: f32, y: f32, value: u32 },
+    NearMiss { x: f32, y: f32, value: u32 },
+    Died,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
 pub struct PhysObj {
     pub x: f32,
     pub y: f32,
@@ -22,13 +36,13 @@ pub struct PhysObj {
 }
 impl PhysObj {
     fn position_delta(&mut self, time_delta: f32) {
-        (*self).x += (*self).x_velocity * time_delta;
-        (*self).y += (*self).y_velocity * time_delta;
+        self.x += self.x_velocity * time_delta;
+        self.y += self.y_velocity * time_delta;
     }
 }
 
-fn rand_range(min: f32, max: f32) -> f32 {
-    rand::random::<f32>() * (max - min) + min
+fn rand_range(rng: &mut impl Rng, min: f32, max: f32) -> f32 {
+    rng.gen::<f32>() * (max - min) + min
 }
 
 fn despawn_objs(phys_objs: &mut Vec<PhysObj>) {
@@ -49,6 +63,16 @@ fn objs_overlap(a: PhysObj, b: PhysObj) -> bool {
         && a.y + a.height > b.y - b.height
 }
 
+// b came within margin of a's hitbox, without actually touching it
+fn objs_near_miss(a: PhysObj, b: PhysObj, margin: f32) -> bool {
+    let grown = PhysObj {
+        width: a.width + margin,
+        height: a.height + margin,
+        ..a
+    };
+    !objs_overlap(a, b) && objs_overlap(grown, b)
+}
+
 fn spawn_obj(
     last_spawn_time: &mut Option<Instant>,
     now: Instant,
@@ -65,10 +89,13 @@ fn spawn_obj(
         (*obj_list).push(next_obj);
         return true;
     }
-    return false;
+    false
 }
 
 pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
+    game_state.events.clear();
+    let last_birdy = game_state.birdy;
+
     // update positions
     game_state.birdy.position_delta(time_delta);
     for rock in game_state.rocks.iter_mut() {
@@ -77,6 +104,19 @@ pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
     for coin in game_state.coins.iter_mut() {
         coin.position_delta(time_delta);
     }
+    for popup in game_state.score_popups.iter_mut() {
+        popup.y += combo::POPUP_VELOCITY * time_delta;
+    }
+    game_state
+        .score_popups
+        .retain(|popup| now.duration_since(popup.spawn_time) < combo::POPUP_LIFETIME);
+
+    // combo timeout
+    if let Some(time) = game_state.last_coin_time {
+        if now.duration_since(time) > combo::WINDOW {
+            game_state.combo = 0;
+        }
+    }
 
     // update player velocity for next frame
     game_state.birdy.x_velocity = if game_state.birdy.x_velocity.is_sign_positive() {
@@ -96,17 +136,17 @@ pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
     );
     for key in game_state.keys.iter() {
         match key {
-            VirtualKeyCode::Space => {
+            VirtualKeyCode::Space
                 if match game_state.last_jump_time {
                     None => Duration::MAX,
                     Some(time) => now.duration_since(time),
-                } > birdy::JUMP_COOLDOWN
-                {
-                    game_state.last_jump_time = Some(now);
-                    game_state.birdy.y_velocity = birdy::ACCEL_JUMP;
-                }
+                } > birdy::JUMP_COOLDOWN =>
+            {
+                game_state.last_jump_time = Some(now);
+                game_state.birdy.y_velocity = birdy::ACCEL_JUMP;
+                game_state.events.push(GameEvent::Jumped);
             }
-            VirtualKeyCode::Left => game_state.birdy.x_velocity = birdy::ACCEL_MOVE * -1.0,
+            VirtualKeyCode::Left => game_state.birdy.x_velocity = -birdy::ACCEL_MOVE,
             VirtualKeyCode::Right => game_state.birdy.x_velocity = birdy::ACCEL_MOVE,
             _ => (),
         }
@@ -121,7 +161,7 @@ pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
         &mut game_state.rocks,
     ) {
         game_state.rock_fall_direction *= -1.0;
-        game_state.next_rock = rock::new_rock(game_state.rock_fall_direction);
+        game_state.next_rock = rock::new_rock(game_state.rock_fall_direction, &mut game_state.rng);
     }
     despawn_objs(&mut game_state.rocks);
 
@@ -133,7 +173,7 @@ pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
         game_state.next_coin,
         &mut game_state.coins,
     ) {
-        game_state.next_coin = coin::new_coin();
+        game_state.next_coin = coin::new_coin(&mut game_state.rng);
     }
     despawn_objs(&mut game_state.coins);
 
@@ -157,18 +197,55 @@ pub fn tick(game_state: &mut GameState, now: Instant, time_delta: f32) {
     while i < game_state.rocks.len() {
         let rock = game_state.rocks[i];
         if objs_overlap(game_state.birdy, rock) {
+            game_state.combo = 0;
             game_state.dead = true;
+            game_state.events.push(GameEvent::Died);
             return; // this round is over, no point in doing anything else
         }
         i += 1;
     }
 
+    // birdy-rock near misses, counted once as the rock passes the birdy
+    for rock in game_state.rocks.iter() {
+        let last_rock_y = rock.y - rock.y_velocity * time_delta;
+        let passed = (last_rock_y < last_birdy.y) != (rock.y < game_state.birdy.y);
+        if passed && objs_near_miss(game_state.birdy, *rock, game_state.near_miss_margin) {
+            let value = rock::NEAR_MISS_BONUS * combo::multiplier(game_state.combo);
+            game_state.score += value;
+            game_state.score_popups.push(combo::ScorePopup {
+                x: rock.x,
+                y: game_state.birdy.y,
+                value,
+                spawn_time: now,
+            });
+            game_state.events.push(GameEvent::NearMiss {
+                x: rock.x,
+                y: game_state.birdy.y,
+                value,
+            });
+        }
+    }
+
     // birdy-coin collision
     let mut i = 0;
     while i < game_state.coins.len() {
         let coin = game_state.coins[i];
         if objs_overlap(game_state.birdy, coin) {
-            game_state.score += 1;
+            game_state.combo += 1;
+            game_state.last_coin_time = Some(now);
+            let value = combo::coin_value(coin) * combo::multiplier(game_state.combo);
+            game_state.score += value;
+            game_state.score_popups.push(combo::ScorePopup {
+                x: coin.x,
+                y: coin.y,
+                value,
+                spawn_time: now,
+            });
+            game_state.events.push(GameEvent::CoinCollected {
+                x: coin.x,
+                y: coin.y,
+                value,
+            });
             game_state.coins.remove(i);
         } else {
             i += 1;
@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+pub const WINDOW: Duration = Duration::from_millis(2000); // how long after a pickup the next coin still extends the combo
+pub const COINS_PER_MULTIPLIER: u32 = 3; // consecutive coins needed to bump the multiplier up by one
+pub const MAX_MULTIPLIER: u32 = 5;
+pub const MAX_COIN_VALUE: u32 = 3; // base value of the smallest, fastest possible coin
+pub const POPUP_LIFETIME: Duration = Duration::from_millis(1000);
+pub const POPUP_VELOCITY: f32 = 0.3; // how fast "+N" popups float upwards
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScorePopup {
+    pub x: f32,
+    pub y: f32,
+    pub value: u32,
+    pub spawn_time: Instant,
+}
+
+pub fn multiplier(combo: u32) -> u32 {
+    u32::min(
+        1 + combo.saturating_sub(1) / COINS_PER_MULTIPLIER,
+        MAX_MULTIPLIER,
+    )
+}
+
+// smaller and faster coins are harder to grab, so they're worth more
+pub fn coin_value(coin: super::PhysObj) -> u32 {
+    let smallness =
+        (super::coin::MAX_SIZE - coin.width) / (super::coin::MAX_SIZE - super::coin::MIN_SIZE);
+    let speed = (coin.y_velocity.abs() - super::coin::MIN_VELOCITY)
+        / (super::coin::MAX_VELOCITY - super::coin::MIN_VELOCITY);
+    let bonus = ((smallness + speed).clamp(0.0, 2.0) / 2.0 * (MAX_COIN_VALUE - 1) as f32).round();
+    1 + bonus as u32
+}
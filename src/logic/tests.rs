@@ -0,0 +1,92 @@
+use super::*;
+
+fn coin(size: f32, velocity: f32) -> PhysObj {
+    PhysObj {
+        x: 0.0,
+        y: 0.0,
+        x_velocity: 0.0,
+        y_velocity: velocity,
+        width: size,
+        height: size,
+    }
+}
+
+#[test]
+fn combo_multiplier() {
+    assert_eq!(1, combo::multiplier(0));
+    assert_eq!(1, combo::multiplier(combo::COINS_PER_MULTIPLIER));
+    assert_eq!(2, combo::multiplier(combo::COINS_PER_MULTIPLIER + 1));
+    assert_eq!(combo::MAX_MULTIPLIER, combo::multiplier(u32::MAX));
+}
+
+#[test]
+fn coin_value() {
+    assert_eq!(
+        1,
+        combo::coin_value(coin(coin::MAX_SIZE, -coin::MIN_VELOCITY))
+    );
+    assert_eq!(
+        combo::MAX_COIN_VALUE,
+        combo::coin_value(coin(coin::MIN_SIZE, coin::MAX_VELOCITY))
+    );
+}
+
+#[test]
+fn combo_builds_and_times_out() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.last_coin_spawn_time = Some(now); // keep new coins out of the way
+    game_state.last_rock_spawn_time = Some(now);
+
+    for i in 0..2 {
+        game_state.coins.push(game_state.birdy);
+        tick(&mut game_state, now + combo::WINDOW / 4 * i, 0.0);
+    }
+    assert_eq!(2, game_state.combo);
+    assert_eq!(2, game_state.score_popups.len());
+
+    tick(&mut game_state, now + combo::WINDOW * 2, 0.0);
+    assert_eq!(0, game_state.combo);
+    assert!(game_state.score_popups.is_empty());
+}
+
+#[test]
+fn near_miss() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.last_coin_spawn_time = Some(now);
+    game_state.last_rock_spawn_time = Some(now);
+    game_state.birdy.y_velocity = 0.0;
+    game_state.near_miss_margin = 0.05;
+    let birdy = game_state.birdy;
+
+    // a rock falling past the birdy's right side, just outside its hitbox
+    let rock = PhysObj {
+        x: birdy.x + birdy.width + rock::MIN_SIZE + game_state.near_miss_margin / 2.0,
+        y: birdy.y + 0.01,
+        x_velocity: 0.0,
+        y_velocity: -1.0,
+        width: rock::MIN_SIZE,
+        height: rock::MIN_SIZE,
+    };
+    assert!(objs_near_miss(birdy, rock, game_state.near_miss_margin));
+    assert!(!objs_near_miss(
+        birdy,
+        PhysObj { x: birdy.x, ..rock },
+        game_state.near_miss_margin
+    ));
+
+    game_state.rocks.push(rock);
+    tick(&mut game_state, now, 0.02);
+    assert!(!game_state.dead);
+    assert_eq!(rock::NEAR_MISS_BONUS, game_state.score);
+    assert!(matches!(
+        game_state.events.as_slice(),
+        [GameEvent::NearMiss { .. }]
+    ));
+
+    // already passed, so no second bonus
+    tick(&mut game_state, now, 0.02);
+    assert_eq!(rock::NEAR_MISS_BONUS, game_state.score);
+    assert!(game_state.events.is_empty());
+}
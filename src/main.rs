@@ -1,11 +1,8 @@
-mod logic;
-mod render;
-
-use crate::logic::PhysObj;
+use flap::{autopilot, control, logic, render, tui, GameState};
 
 use std::time::{Duration, Instant};
 
-use glium::glutin::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use glium::glutin::event::{ElementState, Event, KeyboardInput, WindowEvent};
 use glium::glutin::event_loop::{ControlFlow, EventLoop};
 
 const WINDOW_INITIAL_WIDTH: u32 = 1024;
@@ -13,41 +10,89 @@ const WINDOW_INITIAL_HEIGHT: u32 = 768;
 
 const FRAMERATE: Duration = Duration::from_nanos(8_333_334); // How often a frame should render
 
-#[derive(Clone)]
-pub struct GameState {
-    pub last_jump_time: Option<Instant>,
-    pub last_rock_spawn_time: Option<Instant>,
-    pub rock_fall_direction: f32,
-    pub next_rock: PhysObj,
-    pub last_coin_spawn_time: Option<Instant>,
-    pub next_coin: PhysObj,
-    pub birdy: PhysObj,
-    pub rocks: Vec<PhysObj>,
-    pub coins: Vec<PhysObj>,
-    pub score: u32,
-    pub keys: Vec<VirtualKeyCode>,
-    pub dead: bool,
+const SCREENSHOT_WARMUP: Duration = Duration::from_secs(3); // how long to play before taking a headless screenshot
+const SCREENSHOT_SEED: u64 = 0; // headless screenshots are of the same round every time unless given a --seed
+
+const POST_PROCESS_SETTINGS: &str = "./res/post_process.json";
+
+const FONT: &str = "./res/Octoville.otf";
+const FALLBACK_FONTS_VAR: &str = "FLAP_FALLBACK_FONTS"; // extra fonts for characters Octoville lacks, separated like PATH
+
+// the game's font, followed by any fallback fonts from the environment, ttf, otf or BMFont .fnt
+fn load_fonts() -> Result<Vec<render::font::Font>, Box<dyn std::error::Error>> {
+    let mut paths = vec![std::path::PathBuf::from(FONT)];
+    if let Some(fallbacks) = std::env::var_os(FALLBACK_FONTS_VAR) {
+        paths.extend(std::env::split_paths(&fallbacks));
+    }
+    paths.iter().map(render::font::Font::load).collect()
 }
-impl GameState {
-    pub fn new() -> GameState {
-        GameState {
-            last_jump_time: None,
-            last_rock_spawn_time: None,
-            rock_fall_direction: 1.0,
-            next_rock: logic::rock::new_rock(1.0),
-            last_coin_spawn_time: None,
-            next_coin: logic::coin::new_coin(),
-            birdy: logic::birdy::new_birdy(),
-            rocks: Vec::new(),
-            coins: Vec::new(),
-            score: 0,
-            keys: Vec::new(),
-            dead: false,
-        }
+
+// play for a little while on the autopilot, then draw a single frame on the CPU and save it
+fn headless_screenshot(
+    path: &str,
+    seed: u64,
+    fonts: &[render::font::Font],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let texture_atlas = render::software::Image::load_png("./res/atlas.png")?;
+    let sprite_atlas = render::sprite_atlas::SpriteAtlas::load("./res/atlas.json")?;
+
+    let mut game_state = GameState::with_seed(seed);
+    let mut autopilot = autopilot::Autopilot::new();
+    let start = Instant::now();
+    let mut animator = render::animation::Animator::new(start);
+    let mut now = start;
+    while now.duration_since(start) < SCREENSHOT_WARMUP && !game_state.dead {
+        game_state.keys = autopilot.update(&game_state, now).keys();
+        now += autopilot::TICK_TIME;
+        logic::tick(&mut game_state, now, autopilot::TICK_TIME.as_secs_f32());
+        animator.handle_events(&game_state.events, now);
     }
+
+    render::software::draw(
+        &render::draw_list::build(
+            &game_state,
+            &sprite_atlas,
+            &animator,
+            now,
+            Duration::ZERO,
+            0.0,
+        ),
+        &texture_atlas,
+        fonts,
+        WINDOW_INITIAL_WIDTH,
+        WINDOW_INITIAL_HEIGHT,
+    )
+    .save_png(path)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Load font data from file
+    let fonts = load_fonts()?;
+
+    // no window needed, e.g. --headless-screenshot out.png, or --headless-screenshot out.png --seed 7 for another round
+    if let Some(i) = args.iter().position(|arg| arg == "--headless-screenshot") {
+        let seed = match args.iter().position(|arg| arg == "--seed") {
+            Some(i) => args.get(i + 1).ok_or("--seed needs a number")?.parse()?,
+            None => SCREENSHOT_SEED,
+        };
+        return headless_screenshot(
+            args.get(i + 1)
+                .ok_or("--headless-screenshot needs an output path")?,
+            seed,
+            &fonts,
+        );
+    }
+
+    // play in the terminal instead, --tui for unicode half-blocks or --tui-ascii for plain characters
+    if args.iter().any(|arg| arg == "--tui") {
+        return Ok(tui::run(tui::Style::HalfBlocks)?);
+    }
+    if args.iter().any(|arg| arg == "--tui-ascii") {
+        return Ok(tui::run(tui::Style::Ascii)?);
+    }
+
     // renderer variables
     let mut then = Instant::now();
     let mut window_aspect_ratio = WINDOW_INITIAL_WIDTH as f32 / WINDOW_INITIAL_HEIGHT as f32;
@@ -72,8 +117,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         &std::fs::read_to_string("./res/frag.glsl")?,
         None,
     )?;
+    let field_shdr = glium::program::Program::from_source(
+        &disp,
+        &std::fs::read_to_string("./res/distance_field_vert.glsl")?,
+        &std::fs::read_to_string("./res/distance_field_frag.glsl")?,
+        None,
+    )?;
 
-    // Load texture atlas
+    // Load texture atlas, along with the names and locations of the sprites in it
+    let sprite_atlas = render::sprite_atlas::SpriteAtlas::load("./res/atlas.json")?;
     let decoder = png::Decoder::new(std::fs::File::open("./res/atlas.png")?);
     let mut reader = decoder.read_info()?;
     let mut texture_atlas = vec![0; reader.output_buffer_size()];
@@ -87,13 +139,57 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             format: glium::texture::ClientFormat::U8U8U8U8,
         },
     )?;
-
-    // Load font data from file
-    let font_data: Vec<u8> = std::fs::read("./res/Octoville.otf")?;
-    let font: rusttype::Font<'static> = rusttype::Font::try_from_vec(font_data).unwrap();
+    // how the playfield is scaled up to the window, e.g. --scaling pixel-perfect
+    let scaling = match args.iter().position(|arg| arg == "--scaling") {
+        Some(i) => args
+            .get(i + 1)
+            .ok_or("--scaling needs native, pixel-perfect or smooth")?
+            .parse()?,
+        None => render::scaling::Scaling::default(),
+    };
+    // full-screen effects as the settings have them, or e.g. --post-process bloom,crt for just those, none for none
+    let mut post_process_settings = render::post_process::Settings::load(POST_PROCESS_SETTINGS)?;
+    if let Some(i) = args.iter().position(|arg| arg == "--post-process") {
+        let names: Vec<&str> = args
+            .get(i + 1)
+            .ok_or("--post-process needs a comma separated list of passes")?
+            .split(',')
+            .filter(|name| *name != "none")
+            .collect();
+        post_process_settings.enable_only(&names)?;
+    }
+    let post_processor = render::post_process::PostProcessor::new(
+        &disp,
+        &post_process_settings,
+        std::path::Path::new(POST_PROCESS_SETTINGS)
+            .parent()
+            .unwrap_or(std::path::Path::new(".")),
+    )?;
+    let mut renderer = render::Renderer::new(&disp, shdr, field_shdr, texture_atlas)?
+        .with_scaling(scaling)
+        .with_post_processor(post_processor);
 
     let mut game_state = GameState::new();
 
+    // attract-mode demo, the game plays itself
+    let mut autopilot = if args.iter().any(|arg| arg == "--autopilot") {
+        Some(autopilot::Autopilot::new())
+    } else {
+        None
+    };
+
+    // optional localhost server for scripts and external tools, e.g. --control 127.0.0.1:7777
+    let mut control = match args.iter().position(|arg| arg == "--control") {
+        Some(i) => Some(control::ControlServer::bind(
+            args.get(i + 1)
+                .ok_or("--control needs an address")?
+                .as_str(),
+        )?),
+        None => None,
+    };
+    let mut game_time = Instant::now(); // stands still while paused through the control server
+    let mut animator = render::animation::Animator::new(game_time);
+
     let mut avg_fps = 0.0;
     let mut last_frametime = Duration::ZERO;
 
@@ -145,28 +241,61 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             _ => (),
         }
 
-        logic::tick(&mut game_state, now, time_delta);
+        if let Some(autopilot) = autopilot.as_mut() {
+            game_state.keys = autopilot.update(&game_state, game_time).keys();
+        }
+
+        match control.as_mut() {
+            Some(server) => {
+                server.poll(&game_state);
+                if let Some(action) = server.action() {
+                    game_state.keys = action.keys();
+                }
+                if server.paused() {
+                    for _ in 0..server.take_pending_ticks() {
+                        game_time += control::STEP_TIME;
+                        logic::tick(&mut game_state, game_time, control::STEP_TIME.as_secs_f32());
+                        animator.handle_events(&game_state.events, game_time);
+                        // the round's over, the rest of the step would only tick a dead birdy
+                        if game_state.dead {
+                            break;
+                        }
+                    }
+                } else {
+                    game_time += now.duration_since(then);
+                    logic::tick(&mut game_state, game_time, time_delta);
+                    animator.handle_events(&game_state.events, game_time);
+                }
+            }
+            None => {
+                game_time += now.duration_since(then);
+                logic::tick(&mut game_state, game_time, time_delta);
+                animator.handle_events(&game_state.events, game_time);
+            }
+        }
 
-	if last_frametime_avg_calculation.elapsed() >= Duration::from_secs(1) {
+        if last_frametime_avg_calculation.elapsed() >= Duration::from_secs(1) {
             avg_fps = frame_counter as f32 / last_frametime_avg_calculation.elapsed().as_secs_f32();
             frame_counter = 0;
             last_frametime_avg_calculation = Instant::now();
-	}
-
-        if FRAMERATE * frame_counter <= last_frametime_avg_calculation.elapsed()
-        {
-	    render::draw(
-		&game_state,
-		&disp,
-		&shdr,
-		&texture_atlas,
-		&font,
-		window_aspect_ratio,
-		last_frametime,
-		avg_fps,
+        }
+
+        if FRAMERATE * frame_counter <= last_frametime_avg_calculation.elapsed() {
+            renderer.draw(
+                &render::draw_list::build(
+                    &game_state,
+                    &sprite_atlas,
+                    &animator,
+                    game_time,
+                    last_frametime,
+                    avg_fps,
+                ),
+                &disp,
+                &fonts,
+                window_aspect_ratio,
             );
-	    frame_counter += 1;
-	    last_frametime = now.elapsed();
+            frame_counter += 1;
+            last_frametime = now.elapsed();
         }
 
         then = now;
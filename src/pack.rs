@@ -0,0 +1,135 @@
+use super::render::software::Image;
+use super::render::sprite_atlas::{PixelRect, SpriteAtlas};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests;
+
+pub const DEFAULT_PADDING: u32 = 1; // transparent pixels between neighbouring sprites
+pub const DEFAULT_EXTRUSION: u32 = 1; // how far each sprite's edge pixels get repeated outwards, to stop texture bleeding
+const MAX_ATLAS_SIZE: u32 = 8192;
+
+// find a spot for every size, shelf by shelf, within a square atlas of the given size
+fn pack_rects(sizes: &[(u32, u32)], atlas_size: u32) -> Option<Vec<(u32, u32)>> {
+    // tallest first, so shelves don't waste much space
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| {
+        sizes[b]
+            .1
+            .cmp(&sizes[a].1)
+            .then(sizes[b].0.cmp(&sizes[a].0))
+    });
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut shelf_x, mut shelf_y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (width, height) = sizes[i];
+        if shelf_x + width > atlas_size {
+            // start a new shelf
+            shelf_y += shelf_height;
+            shelf_x = 0;
+            shelf_height = 0;
+        }
+        if shelf_x + width > atlas_size || shelf_y + height > atlas_size {
+            return None;
+        }
+        positions[i] = (shelf_x, shelf_y);
+        shelf_x += width;
+        shelf_height = u32::max(shelf_height, height);
+    }
+    Some(positions)
+}
+
+// pack named sprites into a single atlas image, along with the metadata describing where each one ended up
+pub fn pack(
+    sprites: &[(String, Image)],
+    padding: u32,
+    extrusion: u32,
+) -> Result<(Image, SpriteAtlas), Box<dyn Error>> {
+    let border = extrusion + padding;
+    let sizes: Vec<(u32, u32)> = sprites
+        .iter()
+        .map(|(_, image)| (image.width + border * 2, image.height + border * 2))
+        .collect();
+
+    // smallest power of two square that everything fits in
+    let mut atlas_size = 1;
+    let positions = loop {
+        if let Some(positions) = pack_rects(&sizes, atlas_size) {
+            break positions;
+        }
+        if atlas_size >= MAX_ATLAS_SIZE {
+            return Err(format!("sprites don't fit in a {0}x{0} atlas", MAX_ATLAS_SIZE).into());
+        }
+        atlas_size *= 2;
+    };
+
+    let mut atlas = Image {
+        width: atlas_size,
+        height: atlas_size,
+        data: vec![0; (atlas_size * atlas_size * 4) as usize],
+    };
+    let mut rects = HashMap::new();
+    for ((name, image), (x, y)) in sprites.iter().zip(positions) {
+        let rect = PixelRect {
+            x: x + border,
+            y: y + border,
+            width: image.width,
+            height: image.height,
+        };
+
+        // copy the sprite in, clamping to its edges for the extruded border
+        let extrusion = extrusion as i64;
+        for dest_y in -extrusion..image.height as i64 + extrusion {
+            for dest_x in -extrusion..image.width as i64 + extrusion {
+                let src_x = dest_x.clamp(0, image.width as i64 - 1) as usize;
+                let src_y = dest_y.clamp(0, image.height as i64 - 1) as usize;
+                let src = (src_x + src_y * image.width as usize) * 4;
+                let dest_x = (rect.x as i64 + dest_x) as usize;
+                let dest_y = (rect.y as i64 + dest_y) as usize;
+                let dest = (dest_x + dest_y * atlas_size as usize) * 4;
+                atlas.data[dest..dest + 4].copy_from_slice(&image.data[src..src + 4]);
+            }
+        }
+
+        if rects.insert(name.clone(), rect).is_some() {
+            return Err(format!("more than one sprite named \"{}\"", name).into());
+        }
+    }
+
+    Ok((
+        atlas,
+        SpriteAtlas {
+            width: atlas_size,
+            height: atlas_size,
+            sprites: rects,
+            animations: HashMap::new(),
+        },
+    ))
+}
+
+// every png in a folder, named after its file stem, sorted by name so packing is reproducible
+pub fn load_sprites(dir: impl AsRef<Path>) -> Result<Vec<(String, Image)>, Box<dyn Error>> {
+    let mut sprites = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("png") {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("{} isn't a usable sprite name", path.display()))?
+            .to_string();
+        let image = Image::load_png(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if image.width == 0 || image.height == 0 {
+            return Err(format!("{} is empty", path.display()).into());
+        }
+        sprites.push((name, image));
+    }
+    sprites.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(sprites)
+}
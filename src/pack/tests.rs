@@ -0,0 +1,60 @@
+use super::*;
+
+fn solid(width: u32, height: u32, color: [u8; 4]) -> Image {
+    Image {
+        width,
+        height,
+        data: color.repeat((width * height) as usize),
+    }
+}
+
+fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+    let index = ((x + y * image.width) * 4) as usize;
+    image.data[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn rects_dont_overlap() {
+    let sizes = [(10, 4), (6, 6), (6, 3), (16, 2), (3, 3)];
+    let positions = pack_rects(&sizes, 16).unwrap();
+    for (i, (&(ax, ay), &(aw, ah))) in positions.iter().zip(sizes.iter()).enumerate() {
+        assert!(ax + aw <= 16 && ay + ah <= 16);
+        for (&(bx, by), &(bw, bh)) in positions.iter().zip(sizes.iter()).skip(i + 1) {
+            assert!(ax + aw <= bx || bx + bw <= ax || ay + ah <= by || by + bh <= ay);
+        }
+    }
+    assert_eq!(None, pack_rects(&sizes, 8));
+}
+
+#[test]
+fn pack_with_extrusion() {
+    let red = [255, 0, 0, 255];
+    let blue = [0, 0, 255, 255];
+    let sprites = vec![
+        ("red".to_string(), solid(8, 8, red)),
+        ("blue".to_string(), solid(4, 2, blue)),
+    ];
+    let (atlas, sprite_atlas) = pack(&sprites, 1, 1).unwrap();
+
+    // 12 + 8 pixels across with borders, too wide for 16
+    assert_eq!((32, 32), (atlas.width, atlas.height));
+    assert_eq!((32, 32), (sprite_atlas.width, sprite_atlas.height));
+    let rect = sprite_atlas.sprites["red"];
+    assert_eq!((8, 8), (rect.width, rect.height));
+    assert_eq!(red, pixel(&atlas, rect.x, rect.y));
+    // extruded border, then padding
+    assert_eq!(red, pixel(&atlas, rect.x - 1, rect.y - 1));
+    assert_eq!([0; 4], pixel(&atlas, rect.x - 2, rect.y - 2));
+
+    let rect = sprite_atlas.sprites["blue"];
+    assert_eq!(blue, pixel(&atlas, rect.x + 3, rect.y + 2));
+}
+
+#[test]
+fn duplicate_names() {
+    let sprites = vec![
+        ("coin".to_string(), solid(2, 2, [255; 4])),
+        ("coin".to_string(), solid(2, 2, [255; 4])),
+    ];
+    assert!(pack(&sprites, 0, 0).is_err());
+}
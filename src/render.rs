@@ -1,248 +1,670 @@
 use glium::Surface;
 
+use std::collections::HashMap;
+
 #[cfg(test)]
 mod tests;
 
+pub mod animation;
+pub mod camera;
+pub mod draw_list;
+pub mod font;
+pub mod markup;
+pub mod post_process;
+pub mod scaling;
+pub mod software;
+pub mod sprite_atlas;
 mod text;
 
-const BIRDY_DEPTH: f32 = 0.1;
-const ROCK_DEPTH: f32 = 0.2;
-const COIN_DEPTH: f32 = 0.3;
-
-const PLAYFIELD_MODEL: Quad = square_from_edge_positions(
-    -1.0,
-    1.0,
-    1.0,
-    -1.0,
-    0.9,
-    ((0.0, 0.0), (8.0 / 64.0, 8.0 / 64.0)),
-);
+pub use draw_list::DrawList;
+
+const INITIAL_INSTANCE_CAPACITY: usize = 256; // grows by doubling whenever a frame needs more
 
+// one corner of the shared unit quad, every sprite is drawn as an instance of it
 #[derive(Copy, Clone, Debug, PartialEq)]
-struct Vert {
-    position: (f32, f32, f32),
-    texture_coordinates: (f32, f32),
+struct Corner {
+    corner: (f32, f32),
 }
-glium::implement_vertex!(Vert, position, texture_coordinates);
+glium::implement_vertex!(Corner, corner);
 
-type Quad = [Vert; 6];
-const fn quad_from_verts(lt: Vert, rt: Vert, rb: Vert, lb: Vert) -> Quad {
-    [rt, lt, lb, rt, lb, rb]
-}
+// left top, left bottom, right top, right bottom, as a triangle strip
+const UNIT_QUAD: [Corner; 4] = [
+    Corner {
+        corner: (-1.0, 1.0),
+    },
+    Corner {
+        corner: (-1.0, -1.0),
+    },
+    Corner { corner: (1.0, 1.0) },
+    Corner {
+        corner: (1.0, -1.0),
+    },
+];
 
-fn square_from_dims(
-    width: f32,
-    height: f32,
+// everything vert.glsl needs to place one quad
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Instance {
+    center: (f32, f32),
+    half_size: (f32, f32), // already scaled
+    rotation: f32,
     depth: f32,
+    texture_region: (f32, f32, f32, f32), // left, top, right, bottom, already flipped
+    tint: (f32, f32, f32, f32),
+}
+glium::implement_vertex!(
+    Instance,
+    center,
+    half_size,
+    rotation,
+    depth,
+    texture_region,
+    tint
+);
+
+// everything distance_field_vert.glsl needs to place one glyph and draw its outline and glow
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct FieldInstance {
     center: (f32, f32),
-    texture_coordinates: ((f32, f32), (f32, f32)),
-) -> Quad {
-    let center_x = center.0;
-    let center_y = center.1;
-    quad_from_verts(
-        Vert {
-            position: (center_x - width, center_y + height, depth),
-            texture_coordinates: (texture_coordinates.0 .0, texture_coordinates.0 .1),
-        },
-        Vert {
-            position: (center_x + width, center_y + height, depth),
-            texture_coordinates: (texture_coordinates.1 .0, texture_coordinates.0 .1),
-        },
-        Vert {
-            position: (center_x + width, center_y - height, depth),
-            texture_coordinates: (texture_coordinates.1 .0, texture_coordinates.1 .1),
-        },
-        Vert {
-            position: (center_x - width, center_y - height, depth),
-            texture_coordinates: (texture_coordinates.0 .0, texture_coordinates.1 .1),
-        },
-    )
-}
-
-const fn square_from_edge_positions(
-    left: f32,
-    right: f32,
-    top: f32,
-    bottom: f32,
+    half_size: (f32, f32),
     depth: f32,
-    texture_coordinates: ((f32, f32), (f32, f32)),
-) -> Quad {
-    quad_from_verts(
-        Vert {
-            position: (left, top, depth),
-            texture_coordinates: (texture_coordinates.0 .0, texture_coordinates.0 .1),
-        },
-        Vert {
-            position: (right, top, depth),
-            texture_coordinates: (texture_coordinates.1 .0, texture_coordinates.0 .1),
-        },
-        Vert {
-            position: (right, bottom, depth),
-            texture_coordinates: (texture_coordinates.1 .0, texture_coordinates.1 .1),
-        },
-        Vert {
-            position: (left, bottom, depth),
-            texture_coordinates: (texture_coordinates.0 .0, texture_coordinates.1 .1),
-        },
-    )
-}
-
-pub fn draw(
-    game_state: &super::GameState,
+    texture_region: (f32, f32, f32, f32), // left, top, right, bottom
+    tint: (f32, f32, f32, f32),
+    outline_width: f32,
+    outline_color: (f32, f32, f32, f32),
+    glow_radius: f32,
+    glow_color: (f32, f32, f32, f32),
+}
+glium::implement_vertex!(
+    FieldInstance,
+    center,
+    half_size,
+    depth,
+    texture_region,
+    tint,
+    outline_width,
+    outline_color,
+    glow_radius,
+    glow_color
+);
+
+fn instance_from_sprite(sprite: &draw_list::Sprite) -> Instance {
+    let ((left, top), (right, bottom)) = sprite.flipped_texture_region();
+    Instance {
+        center: sprite.center,
+        half_size: (
+            sprite.half_size.0 * sprite.transform.scale.0,
+            sprite.half_size.1 * sprite.transform.scale.1,
+        ),
+        rotation: sprite.transform.rotation,
+        depth: sprite.depth,
+        texture_region: (left, top, right, bottom),
+        tint: sprite.tint,
+    }
+}
+
+fn instance_from_glyph(glyph: &text::GlyphQuad) -> Instance {
+    let ((left, top), (right, bottom)) = glyph.texture_region;
+    Instance {
+        center: (
+            (glyph.left + glyph.right) / 2.0,
+            (glyph.top + glyph.bottom) / 2.0,
+        ),
+        half_size: (
+            (glyph.right - glyph.left) / 2.0,
+            (glyph.top - glyph.bottom) / 2.0,
+        ),
+        rotation: 0.0,
+        depth: glyph.depth,
+        texture_region: (left, top, right, bottom),
+        tint: glyph.color,
+    }
+}
+
+fn instance_from_field_glyph(glyph: &text::distance_field::FieldQuad) -> FieldInstance {
+    let instance = instance_from_glyph(&glyph.quad);
+    FieldInstance {
+        center: instance.center,
+        half_size: instance.half_size,
+        depth: instance.depth,
+        texture_region: instance.texture_region,
+        tint: instance.tint,
+        outline_width: glyph.effects.outline_width,
+        outline_color: glyph.effects.outline_color,
+        glow_radius: glyph.effects.glow_radius,
+        glow_color: glyph.effects.glow_color,
+    }
+}
+
+// upload a frame's instances, reallocating only when they don't fit anymore
+fn upload<T: glium::Vertex>(
     disp: &glium::Display,
-    shdr: &glium::program::Program,
-    texture_atlas: &glium::texture::srgb_texture2d::SrgbTexture2d,
-    font: &rusttype::Font,
-    window_aspect_ratio: f32,
-    last_frametime: std::time::Duration,
-    avg_fps: f32,
+    buffer: &mut glium::VertexBuffer<T>,
+    instances: &[T],
 ) {
-    // get all our vertices together
-    let mut vertices = Vec::new();
-    vertices.extend_from_slice(&square_from_dims(
-        game_state.birdy.width,
-        game_state.birdy.height,
-        BIRDY_DEPTH,
-        (game_state.birdy.x, game_state.birdy.y),
-        ((8.0 / 64.0, 0.0 / 64.0), (16.0 / 64.0, 8.0 / 64.0)),
-    ));
-    for rock in game_state.rocks.iter() {
-        // rocks, duh
-        if rock.y_velocity.is_sign_positive() {
-            vertices.extend_from_slice(&square_from_dims(
-                rock.width,
-                rock.height,
-                ROCK_DEPTH,
-                (rock.x, rock.y),
-                ((16.0 / 64.0, 32.0 / 64.0), (32.0 / 64.0, 48.0 / 64.0)),
-            ));
-        } else {
-            vertices.extend_from_slice(&square_from_dims(
-                rock.width,
-                rock.height,
-                ROCK_DEPTH,
-                (rock.x, rock.y),
-                ((0.0 / 64.0, 32.0 / 64.0), (16.0 / 64.0, 48.0 / 64.0)),
-            ));
-        }
+    if instances.len() > buffer.len() {
+        *buffer =
+            glium::VertexBuffer::empty_dynamic(disp, instances.len().next_power_of_two()).unwrap();
+    }
+    if let Some(slice) = buffer.slice(0..instances.len()) {
+        slice.write(instances);
     }
-    for coin in game_state.coins.iter() {
-        // coins, duh
-        vertices.extend_from_slice(&square_from_dims(
-            coin.width,
-            coin.height,
-            COIN_DEPTH,
-            (coin.x, coin.y),
-            ((32.0 / 64.0, 32.0 / 64.0), (48.0 / 64.0, 48.0 / 64.0)),
+}
+
+// glue a text run's spans together into a single string
+fn super_string_from_run<'a>(
+    text_run: &draw_list::TextRun,
+    fonts: &'a [font::Font],
+) -> text::SuperString<'a> {
+    let mut text =
+        text::SuperString::new(String::new(), fonts, Vec::new(), text_run.normalized_height);
+    for span in text_run.spans.iter() {
+        text.cat(text::SuperString::new(
+            span.text.clone(),
+            fonts,
+            vec![text::StyleSpan::new(
+                0..span.text.chars().count(),
+                span.style,
+            )],
+            text_run.normalized_height,
         ));
     }
-    vertices.extend_from_slice(&PLAYFIELD_MODEL); // playfield
-
-    // render game elements to framebuffer
-    let mut f_buff = disp.draw(); // next framebuffer
-    f_buff.clear(
-        // clear the framebuffer
-        None,                       // rect
-        Some((0.0, 0.0, 0.0, 0.0)), // color
-        true,                       // color_srgb
-        Some(f32::MAX),             // depth
-        None,                       // stencil
-    );
-
-    // primary draw, command, render playfield, coins, rocks and player
-    f_buff
-        .draw(
-            // draw vertices to framebuffer
-            &glium::VertexBuffer::new(disp, &vertices).unwrap(),
-            &glium::index::NoIndices(
-                // not using indexed rendering
-                glium::index::PrimitiveType::TrianglesList, // polygon type
-            ),
-            shdr, // shader program
-            &glium::uniform! {
-                window_aspect_ratio: window_aspect_ratio,
-                texture_atlas: texture_atlas.sampled()
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            },
-            &glium::DrawParameters {
-                // draw parameters
-                depth: glium::Depth {
-                    test: glium::draw_parameters::DepthTest::IfLess,
-                    write: true,
+    text.with_layout(text_run.layout)
+        .with_effects(text_run.effects)
+}
+
+// where a text run goes in the space the playfield is drawn in, with screen space anchors worked out for the window's
+// shape, refers to the same corner as a playfield position would
+fn position_of_run(
+    text_run: &draw_list::TextRun,
+    text: &text::SuperString,
+    window_aspect_ratio: f32,
+) -> (f32, f32) {
+    match text_run.space {
+        draw_list::Space::Playfield => text_run.position,
+        draw_list::Space::Screen(anchor) => {
+            let size = text.measure();
+            let (left, top) = anchor.top_left(
+                text_run.position,
+                size,
+                draw_list::screen_half_size(window_aspect_ratio),
+            );
+            (left + text_run.layout.align.offset(size.0), top)
+        }
+    }
+}
+
+// width and height of a text run in normalized units, without drawing it
+pub fn measure(text_run: &draw_list::TextRun, fonts: &[font::Font]) -> (f32, f32) {
+    super_string_from_run(text_run, fonts).measure()
+}
+
+// characters in a text run that none of the fonts have, worth a warning before they show up as boxes
+pub fn missing_characters(text_run: &draw_list::TextRun, fonts: &[font::Font]) -> Vec<char> {
+    super_string_from_run(text_run, fonts).missing_characters()
+}
+
+// how a draw call maps its positions onto the window
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Pass {
+    window_aspect_ratio: f32,
+    view: camera::Matrix, // the camera for the playfield, identity for screen space
+    projection: camera::Matrix,
+    clip_to_playfield: bool, // anything outside of it gets cut off, screen space text doesn't
+}
+
+// low resolution stand-in for the window, the playfield gets drawn here before it's scaled up
+struct PlayfieldTarget {
+    color: glium::texture::srgb_texture2d::SrgbTexture2d,
+    depth: glium::framebuffer::DepthRenderBuffer,
+}
+impl PlayfieldTarget {
+    // square, so the playfield fills it exactly
+    fn new(disp: &glium::Display) -> Result<Self, Box<dyn std::error::Error>> {
+        let resolution = scaling::PLAYFIELD_RESOLUTION;
+        Ok(PlayfieldTarget {
+            color: glium::texture::srgb_texture2d::SrgbTexture2d::empty(
+                disp, resolution, resolution,
+            )?,
+            depth: glium::framebuffer::DepthRenderBuffer::new(
+                disp,
+                glium::texture::DepthFormat::I24,
+                resolution,
+                resolution,
+            )?,
+        })
+    }
+}
+
+// draws draw lists to the window, holding on to its gpu buffers between frames
+pub struct Renderer {
+    shdr: glium::program::Program,
+    field_shdr: glium::program::Program, // for distance field text
+    texture_atlas: glium::texture::srgb_texture2d::SrgbTexture2d,
+    unit_quad: glium::VertexBuffer<Corner>,
+    instances: glium::VertexBuffer<Instance>,
+    glyph_cache: text::GlyphCache,
+    field_instances: glium::VertexBuffer<FieldInstance>,
+    distance_field_cache: text::distance_field::DistanceFieldCache,
+    bitmap_pages: HashMap<(usize, usize), glium::texture::srgb_texture2d::SrgbTexture2d>, // by font and page index
+    white: glium::texture::srgb_texture2d::SrgbTexture2d, // for drawing solid colors, like underlines
+    staging: Vec<Instance>, // reused every frame to gather instances before uploading them
+    field_staging: Vec<FieldInstance>,
+    scaling: scaling::Scaling,
+    playfield_target: Option<PlayfieldTarget>, // made the first time it's needed
+    post_processor: Option<post_process::PostProcessor>,
+}
+impl Renderer {
+    pub fn new(
+        disp: &glium::Display,
+        shdr: glium::program::Program,
+        field_shdr: glium::program::Program,
+        texture_atlas: glium::texture::srgb_texture2d::SrgbTexture2d,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Renderer {
+            shdr,
+            field_shdr,
+            texture_atlas,
+            unit_quad: glium::VertexBuffer::new(disp, &UNIT_QUAD)?,
+            instances: glium::VertexBuffer::empty_dynamic(disp, INITIAL_INSTANCE_CAPACITY)?,
+            glyph_cache: text::GlyphCache::new(disp)?,
+            field_instances: glium::VertexBuffer::empty_dynamic(disp, INITIAL_INSTANCE_CAPACITY)?,
+            distance_field_cache: text::distance_field::DistanceFieldCache::new(disp)?,
+            bitmap_pages: HashMap::new(),
+            white: glium::texture::srgb_texture2d::SrgbTexture2d::new(
+                disp,
+                glium::texture::RawImage2d::from_raw_rgba(vec![255u8; 4], (1, 1)),
+            )?,
+            staging: Vec::with_capacity(INITIAL_INSTANCE_CAPACITY),
+            field_staging: Vec::with_capacity(INITIAL_INSTANCE_CAPACITY),
+            scaling: scaling::Scaling::default(),
+            playfield_target: None,
+            post_processor: None,
+        })
+    }
+
+    pub fn with_scaling(mut self, scaling: scaling::Scaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn with_post_processor(mut self, post_processor: post_process::PostProcessor) -> Self {
+        self.post_processor = Some(post_processor);
+        self
+    }
+
+    // draw a range of the uploaded instances with one draw call
+    fn draw_instances(
+        &self,
+        f_buff: &mut impl Surface,
+        range: std::ops::Range<usize>,
+        texture: &glium::texture::srgb_texture2d::SrgbTexture2d,
+        pass: Pass,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let instances = self.instances.slice(range).unwrap();
+        f_buff
+            .draw(
+                (&self.unit_quad, instances.per_instance().unwrap()),
+                glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+                &self.shdr,
+                &glium::uniform! {
+                    view: pass.view,
+                    projection: pass.projection,
+                    clip_to_playfield: pass.clip_to_playfield,
+                    texture_atlas: texture.sampled()
+                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+                },
+                &glium::DrawParameters {
+                    depth: glium::Depth {
+                        test: glium::draw_parameters::DepthTest::IfLess,
+                        write: true,
+                        ..Default::default()
+                    },
+                    blend: glium::draw_parameters::Blend::alpha_blending(),
                     ..Default::default()
                 },
-                blend: glium::draw_parameters::Blend::alpha_blending(),
-                ..Default::default()
+            )
+            .unwrap();
+    }
+
+    // bitmap font pages go to the gpu the first time something's drawn from them
+    fn upload_bitmap_page(
+        &mut self,
+        disp: &glium::Display,
+        fonts: &[font::Font],
+        font: usize,
+        page: usize,
+    ) {
+        if self.bitmap_pages.contains_key(&(font, page)) {
+            return;
+        }
+        if let font::Font::Bitmap(bitmap_font) = &fonts[font] {
+            let image = &bitmap_font.pages[page];
+            let texture = glium::texture::srgb_texture2d::SrgbTexture2d::new(
+                disp,
+                glium::texture::RawImage2d {
+                    data: std::borrow::Cow::from(&image.data),
+                    width: image.width,
+                    height: image.height,
+                    format: glium::texture::ClientFormat::U8U8U8U8,
+                },
+            )
+            .unwrap();
+            self.bitmap_pages.insert((font, page), texture);
+        }
+    }
+
+    // draw the uploaded distance field glyphs, every glyph's effects first and then every glyph's fill on top
+    fn draw_field_glyphs(&self, f_buff: &mut impl Surface, count: usize, pass: Pass) {
+        if count == 0 {
+            return;
+        }
+        let instances = self.field_instances.slice(0..count).unwrap();
+        for effects_pass in [true, false] {
+            f_buff
+                .draw(
+                    (&self.unit_quad, instances.per_instance().unwrap()),
+                    glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+                    &self.field_shdr,
+                    &glium::uniform! {
+                        view: pass.view,
+                        projection: pass.projection,
+                        clip_to_playfield: pass.clip_to_playfield,
+                        distance_field: self.distance_field_cache.texture().sampled()
+                            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                            .minify_filter(glium::uniforms::MinifySamplerFilter::Linear),
+                        effects_pass: effects_pass,
+                    },
+                    &glium::DrawParameters {
+                        // overlapping glyphs blend instead of hiding each other, they're above everything else anyway
+                        depth: glium::Depth {
+                            test: glium::draw_parameters::DepthTest::IfLessOrEqual,
+                            write: false,
+                            ..Default::default()
+                        },
+                        blend: glium::draw_parameters::Blend::alpha_blending(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+    }
+
+    // submit a draw list to the window
+    pub fn draw(
+        &mut self,
+        draw_list: &DrawList,
+        disp: &glium::Display,
+        fonts: &[font::Font],
+        window_aspect_ratio: f32,
+    ) {
+        let mut f_buff = disp.draw(); // next framebuffer
+        let size = f_buff.get_dimensions();
+        match self.post_processor.take() {
+            // drawn offscreen first, then the last pass puts it on the window, minimized windows have nothing to draw to
+            Some(mut post_processor) if post_processor.is_active() && size.0 > 0 && size.1 > 0 => {
+                post_processor.resize(disp, size).unwrap();
+                self.draw_frame(
+                    &mut post_processor.scene(disp),
+                    draw_list,
+                    disp,
+                    fonts,
+                    window_aspect_ratio,
+                );
+                if post_processor.wants_bloom_mask() {
+                    self.draw_bloom_mask(
+                        &mut post_processor.bloom_mask(disp),
+                        draw_list,
+                        disp,
+                        window_aspect_ratio,
+                    );
+                }
+                post_processor.run(disp, &mut f_buff);
+                self.post_processor = Some(post_processor);
+            }
+            post_processor => {
+                self.post_processor = post_processor;
+                self.draw_frame(&mut f_buff, draw_list, disp, fonts, window_aspect_ratio);
+            }
+        }
+
+        f_buff.finish().unwrap(); // swap framebuffers
+    }
+
+    // draw everything in a draw list to the window, or wherever the post processor wants it first
+    fn draw_frame(
+        &mut self,
+        f_buff: &mut impl Surface,
+        draw_list: &DrawList,
+        disp: &glium::Display,
+        fonts: &[font::Font],
+        window_aspect_ratio: f32,
+    ) {
+        f_buff.clear(
+            // clear the framebuffer
+            None,                       // rect
+            Some((0.0, 0.0, 0.0, 0.0)), // color
+            true,                       // color_srgb
+            Some(f32::MAX),             // depth
+            None,                       // stencil
+        );
+
+        let (playfield_runs, screen_runs): (Vec<_>, Vec<_>) = draw_list
+            .text
+            .iter()
+            .partition(|text_run| text_run.space == draw_list::Space::Playfield);
+        let playfield_pass = Pass {
+            window_aspect_ratio,
+            view: draw_list.camera.view_matrix(),
+            projection: camera::projection_matrix(window_aspect_ratio),
+            clip_to_playfield: true,
+        };
+        match self.scaling {
+            scaling::Scaling::Native => self.draw_pass(
+                f_buff,
+                disp,
+                fonts,
+                &draw_list.sprites,
+                playfield_runs,
+                playfield_pass,
+            ),
+            scaling::Scaling::PixelPerfect | scaling::Scaling::Smooth => {
+                let target = match self.playfield_target.take() {
+                    Some(target) => target,
+                    None => PlayfieldTarget::new(disp).unwrap(),
+                };
+                let mut framebuffer = glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+                    disp,
+                    &target.color,
+                    &target.depth,
+                )
+                .unwrap();
+                framebuffer.clear(None, Some((0.0, 0.0, 0.0, 0.0)), true, Some(f32::MAX), None);
+                self.draw_pass(
+                    &mut framebuffer,
+                    disp,
+                    fonts,
+                    &draw_list.sprites,
+                    playfield_runs,
+                    Pass {
+                        window_aspect_ratio: 1.0,
+                        projection: camera::projection_matrix(1.0),
+                        ..playfield_pass
+                    },
+                );
+                let rect = scaling::playfield_rect(self.scaling, f_buff.get_dimensions());
+                let filter = if self.scaling == scaling::Scaling::PixelPerfect {
+                    glium::uniforms::MagnifySamplerFilter::Nearest
+                } else {
+                    glium::uniforms::MagnifySamplerFilter::Linear
+                };
+                framebuffer.blit_whole_color_to(
+                    &*f_buff,
+                    &glium::BlitTarget {
+                        left: rect.left,
+                        bottom: rect.bottom,
+                        width: rect.width as i32,
+                        height: rect.height as i32,
+                    },
+                    filter,
+                );
+                drop(framebuffer);
+                self.playfield_target = Some(target);
+            }
+        }
+        // screen space text goes on top of everything, bars included
+        f_buff.clear_depth(f32::MAX);
+        self.draw_pass(
+            f_buff,
+            disp,
+            fonts,
+            &[],
+            screen_runs,
+            Pass {
+                window_aspect_ratio,
+                view: camera::IDENTITY_MATRIX,
+                projection: camera::projection_matrix(window_aspect_ratio),
+                clip_to_playfield: false,
+            },
+        );
+    }
+
+    // sprites that glow in their own color, the rest in black so they still hide whatever glows behind them, lined up
+    // with wherever the playfield ended up in the frame
+    fn draw_bloom_mask(
+        &mut self,
+        f_buff: &mut impl Surface,
+        draw_list: &DrawList,
+        disp: &glium::Display,
+        window_aspect_ratio: f32,
+    ) {
+        f_buff.clear(None, Some((0.0, 0.0, 0.0, 0.0)), true, Some(f32::MAX), None);
+        let mut instances = std::mem::take(&mut self.staging);
+        instances.clear();
+        instances.extend(draw_list.sprites.iter().map(|sprite| {
+            let (red, green, blue, alpha) = sprite.tint;
+            Instance {
+                tint: (
+                    red * sprite.bloom,
+                    green * sprite.bloom,
+                    blue * sprite.bloom,
+                    alpha,
+                ),
+                ..instance_from_sprite(sprite)
+            }
+        }));
+        upload(disp, &mut self.instances, &instances);
+        self.staging = instances;
+
+        let size = f_buff.get_dimensions();
+        self.draw_instances(
+            f_buff,
+            0..draw_list.sprites.len(),
+            &self.texture_atlas,
+            Pass {
+                window_aspect_ratio,
+                view: draw_list.camera.view_matrix(),
+                projection: scaling::projection_onto(
+                    scaling::playfield_rect(self.scaling, size),
+                    size,
+                ),
+                clip_to_playfield: true,
             },
-        )
-        .unwrap();
-
-    // create and render our text strings
-
-    // FPS: {avg_fps} {last_framtime}
-    let fps_text = {
-        let mut prefix = text::SuperString::new("FPS: ".to_string(), font, Vec::new(), 1.0 / 15.0);
-        let avg_fps = if avg_fps != 0.0 {
-            format!("{:.1} ", avg_fps)
-        } else {
-            "... ".to_string()
+        );
+    }
+
+    // draw sprites and text that share a camera, quads for a pass are only built once the previous pass is drawn, so
+    // the glyph caches are free to grow in between
+    fn draw_pass(
+        &mut self,
+        f_buff: &mut impl Surface,
+        disp: &glium::Display,
+        fonts: &[font::Font],
+        sprites: &[draw_list::Sprite],
+        text_runs: Vec<&draw_list::TextRun>,
+        pass: Pass,
+    ) {
+        // sprites first, then one quad per glyph or icon, then one per underline or strikethrough
+        let (bitmap_runs, field_runs): (Vec<_>, Vec<_>) = text_runs
+            .into_iter()
+            .partition(|text_run| text_run.rendering == draw_list::TextRendering::Bitmap);
+        let texts = |text_runs: Vec<&draw_list::TextRun>| -> Vec<_> {
+            text_runs
+                .into_iter()
+                .map(|text_run| {
+                    let text = super_string_from_run(text_run, fonts);
+                    let position = position_of_run(text_run, &text, pass.window_aspect_ratio);
+                    (text, position)
+                })
+                .collect()
         };
-        let avg_fps = text::SuperString::new(
-            avg_fps,
-            font,
-            vec![text::ColorFmt::new(0, (0.0, 1.0, 0.0, 1.0))],
-            1.0 / 15.0,
+        let framebuffer_height = f_buff.get_dimensions().1;
+        let text_quads = self
+            .glyph_cache
+            .quads(disp, &texts(bitmap_runs), framebuffer_height);
+        let field_quads =
+            self.distance_field_cache
+                .quads(disp, &texts(field_runs), framebuffer_height);
+        // bitmap font glyphs grouped by the page they sample, one draw each, keeping their order otherwise
+        let mut bitmap_glyphs = text_quads.bitmap_glyphs;
+        bitmap_glyphs.extend(field_quads.bitmap_glyphs);
+        bitmap_glyphs.sort_by_key(|glyph| (glyph.font, glyph.page));
+        for glyph in bitmap_glyphs.iter() {
+            self.upload_bitmap_page(disp, fonts, glyph.font, glyph.page);
+        }
+        let mut instances = std::mem::take(&mut self.staging);
+        instances.clear();
+        instances.extend(sprites.iter().map(instance_from_sprite));
+        instances.extend(text_quads.glyphs.iter().map(instance_from_glyph));
+        instances.extend(
+            bitmap_glyphs
+                .iter()
+                .map(|glyph| instance_from_glyph(&glyph.quad)),
         );
-        let last_frametime = text::SuperString::new(
-            format!("{:?}", last_frametime),
-            font,
-            vec![text::ColorFmt::new(0, (1.0, 1.0, 0.0, 1.0))],
-            1.0 / 15.0,
+        instances.extend(text_quads.icons.iter().map(instance_from_glyph));
+        instances.extend(field_quads.icons.iter().map(instance_from_glyph));
+        instances.extend(text_quads.strokes.iter().map(instance_from_glyph));
+        instances.extend(field_quads.strokes.iter().map(instance_from_glyph));
+        upload(disp, &mut self.instances, &instances);
+        self.staging = instances;
+        let mut field_instances = std::mem::take(&mut self.field_staging);
+        field_instances.clear();
+        field_instances.extend(field_quads.glyphs.iter().map(instance_from_field_glyph));
+        upload(disp, &mut self.field_instances, &field_instances);
+        self.field_staging = field_instances;
+
+        // primary draw command, render playfield, coins, rocks and player in one go
+        let sprite_count = sprites.len();
+        self.draw_instances(f_buff, 0..sprite_count, &self.texture_atlas, pass);
+        // and all of the text in another
+        let glyph_end = sprite_count + text_quads.glyphs.len();
+        self.draw_instances(
+            f_buff,
+            sprite_count..glyph_end,
+            self.glyph_cache.texture(),
+            pass,
         );
-        prefix.cat(avg_fps);
-        prefix.cat(last_frametime);
-        prefix
-    };
-
-    // render the FPS info to the framebuffer
-    text::render_text(
-        &mut f_buff,
-        disp,
-        shdr,
-        window_aspect_ratio,
-        fps_text,
-        (-1.0, 1.0),
-    );
-
-    // Score: {score}
-    // let score_text = {
-    //     let mut prefix =
-    //         text::SuperString::new("Score: ".to_string(), font, Vec::new(), 1.0 / 10.0);
-    //     let score = text::SuperString::new(
-    //         format!("{}", game_state.score),
-    //         font,
-    //         vec![text::ColorFmt::new(0, (0.0, 1.0, 0.0, 1.0))],
-    //         1.0 / 10.0,
-    //     );
-    //     prefix.cat(score);
-    //     prefix
-    // };
-    let score_text = text::SuperString::new(
-        format!("Score: {}", game_state.score),
-        font,
-        vec![text::ColorFmt::new(6, (0.0, 1.0, 0.0, 1.0))],
-        1.0 / 10.0,
-    );
-
-    // render the score info to the framebuffer
-    text::render_text(
-        &mut f_buff,
-        disp,
-        shdr,
-        window_aspect_ratio,
-        score_text,
-        (-1.0, 1.0 - (1.0 / 15.0)),
-    );
-
-    f_buff.finish().unwrap(); // swap framebuffers
+        let mut bitmap_start = glyph_end;
+        for page_glyphs in bitmap_glyphs.chunk_by(|a, b| (a.font, a.page) == (b.font, b.page)) {
+            let page = (page_glyphs[0].font, page_glyphs[0].page);
+            self.draw_instances(
+                f_buff,
+                bitmap_start..bitmap_start + page_glyphs.len(),
+                &self.bitmap_pages[&page],
+                pass,
+            );
+            bitmap_start += page_glyphs.len();
+        }
+        let icon_end = bitmap_start + text_quads.icons.len() + field_quads.icons.len();
+        self.draw_instances(f_buff, bitmap_start..icon_end, &self.texture_atlas, pass);
+        self.draw_field_glyphs(f_buff, field_quads.glyphs.len(), pass);
+        self.draw_instances(
+            f_buff,
+            icon_end..icon_end + text_quads.strokes.len() + field_quads.strokes.len(),
+            &self.white,
+            pass,
+        );
+    }
 }
@@ -0,0 +1,172 @@
+use super::super::logic::GameEvent;
+use super::camera::Camera;
+use super::draw_list::WHITE;
+use super::sprite_atlas::SpriteAtlas;
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+pub const HIT_FLASH: Duration = Duration::from_millis(400); // how long the birdy stays tinted after getting hit
+pub const HIT_SHAKE: f32 = 0.03; // how far the camera jolts right as the birdy gets hit, in playfield units
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayMode {
+    Loop,     // start over after the last frame
+    Once,     // stop on the last frame
+    PingPong, // play forwards, then backwards, then forwards...
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Frame {
+    pub sprite: String, // name of a sprite in the same atlas
+    pub duration_ms: u64,
+}
+
+// named sequence of atlas sprites, lives in the atlas metadata next to the sprites themselves
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clip {
+    pub mode: PlayMode,
+    pub frames: Vec<Frame>,
+}
+impl Clip {
+    // frame indices in the order they're shown, ping-pong plays the inner frames twice per cycle
+    fn sequence(&self) -> Vec<usize> {
+        let mut sequence: Vec<usize> = (0..self.frames.len()).collect();
+        if self.mode == PlayMode::PingPong {
+            sequence.extend((1..self.frames.len().saturating_sub(1)).rev());
+        }
+        sequence
+    }
+
+    // length of one full cycle
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(
+            self.sequence()
+                .into_iter()
+                .map(|i| self.frames[i].duration_ms)
+                .sum(),
+        )
+    }
+
+    // frame showing the given amount of time after the clip started
+    pub fn frame_at(&self, elapsed: Duration) -> &Frame {
+        let sequence = self.sequence();
+        let cycle = self.duration().as_millis().max(1);
+        let mut time = match self.mode {
+            PlayMode::Once if elapsed.as_millis() >= cycle => {
+                return &self.frames[self.frames.len() - 1];
+            }
+            PlayMode::Once => elapsed.as_millis(),
+            PlayMode::Loop | PlayMode::PingPong => elapsed.as_millis() % cycle,
+        };
+        for i in sequence {
+            let duration = self.frames[i].duration_ms as u128;
+            if time < duration {
+                return &self.frames[i];
+            }
+            time -= duration;
+        }
+        &self.frames[self.frames.len() - 1]
+    }
+}
+
+// keeps track of which clips are playing, fed by game events after every tick
+#[derive(Clone, Debug)]
+pub struct Animator {
+    epoch: Instant, // looping clips are timed from here
+    birdy_flap_start: Option<Instant>,
+    birdy_hit_start: Option<Instant>,
+}
+impl Animator {
+    pub fn new(now: Instant) -> Self {
+        Animator {
+            epoch: now,
+            birdy_flap_start: None,
+            birdy_hit_start: None,
+        }
+    }
+
+    pub fn handle_events(&mut self, events: &[GameEvent], now: Instant) {
+        for event in events {
+            match event {
+                GameEvent::Jumped => self.birdy_flap_start = Some(now),
+                GameEvent::Died => {
+                    self.birdy_flap_start = None;
+                    self.birdy_hit_start = Some(now);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // current birdy sprite, flapping for a moment after every jump
+    pub fn birdy_sprite<'a>(&self, sprite_atlas: &'a SpriteAtlas, now: Instant) -> &'a str {
+        match (
+            self.birdy_flap_start,
+            sprite_atlas.animations.get("birdy_flap"),
+        ) {
+            (Some(start), Some(clip)) => {
+                &clip.frame_at(now.saturating_duration_since(start)).sprite
+            }
+            _ => "birdy",
+        }
+    }
+
+    // birdy tint, flashing red after getting hit and fading back to white
+    pub fn birdy_tint(&self, now: Instant) -> (f32, f32, f32, f32) {
+        let Some(start) = self.birdy_hit_start else {
+            return WHITE;
+        };
+        let progress =
+            (now.saturating_duration_since(start).as_secs_f32() / HIT_FLASH.as_secs_f32()).min(1.0);
+        (1.0, progress, progress, 1.0)
+    }
+
+    // camera, shaking after the birdy gets hit and settling down as the flash fades
+    pub fn camera(&self, now: Instant) -> Camera {
+        let Some(start) = self.birdy_hit_start else {
+            return Camera::IDENTITY;
+        };
+        let elapsed = now.saturating_duration_since(start).as_secs_f32();
+        let strength = HIT_SHAKE * f32::max(1.0 - elapsed / HIT_FLASH.as_secs_f32(), 0.0);
+        Camera {
+            // two frequencies that don't line up, so it doesn't just wobble along a line
+            position: (
+                (elapsed * 97.0).sin() * strength,
+                (elapsed * 71.0).sin() * strength,
+            ),
+            ..Camera::IDENTITY
+        }
+    }
+
+    // how far something turning at speed radians per second has got, staggered by phase (0.0..1.0) like looping clips
+    pub fn spin(&self, now: Instant, speed: f32, phase: f32) -> f32 {
+        let elapsed = now.saturating_duration_since(self.epoch).as_secs_f32();
+        (elapsed * speed + phase.rem_euclid(1.0) * std::f32::consts::TAU)
+            .rem_euclid(std::f32::consts::TAU)
+    }
+
+    // current frame of a clip that plays forever, phase (0.0..1.0) staggers objects so they don't all move in lockstep
+    pub fn looping_sprite<'a>(
+        &self,
+        sprite_atlas: &'a SpriteAtlas,
+        clip: &str,
+        fallback: &'a str,
+        now: Instant,
+        phase: f32,
+    ) -> &'a str {
+        match sprite_atlas.animations.get(clip) {
+            Some(clip) => {
+                let elapsed = now.saturating_duration_since(self.epoch)
+                    + clip.duration().mul_f32(phase.rem_euclid(1.0));
+                &clip.frame_at(elapsed).sprite
+            }
+            None => fallback,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::*;
+
+fn clip(mode: PlayMode) -> Clip {
+    Clip {
+        mode,
+        frames: ["a", "b", "c"]
+            .iter()
+            .map(|sprite| Frame {
+                sprite: sprite.to_string(),
+                duration_ms: 100,
+            })
+            .collect(),
+    }
+}
+
+fn sprites_at(clip: &Clip, times_ms: &[u64]) -> Vec<String> {
+    times_ms
+        .iter()
+        .map(|&ms| clip.frame_at(Duration::from_millis(ms)).sprite.clone())
+        .collect()
+}
+
+#[test]
+fn play_modes() {
+    let times = [0, 150, 250, 350, 450, 550, 650];
+    assert_eq!(
+        vec!["a", "b", "c", "a", "b", "c", "a"],
+        sprites_at(&clip(PlayMode::Loop), &times)
+    );
+    assert_eq!(
+        vec!["a", "b", "c", "c", "c", "c", "c"],
+        sprites_at(&clip(PlayMode::Once), &times)
+    );
+    assert_eq!(
+        vec!["a", "b", "c", "b", "a", "b", "c"],
+        sprites_at(&clip(PlayMode::PingPong), &times)
+    );
+    assert_eq!(
+        Duration::from_millis(400),
+        clip(PlayMode::PingPong).duration()
+    );
+}
+
+#[test]
+fn birdy_flaps_after_jumping() {
+    let now = Instant::now();
+    let sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    let flap = &sprite_atlas.animations["birdy_flap"];
+    let mut animator = Animator::new(now);
+    assert_eq!("birdy", animator.birdy_sprite(&sprite_atlas, now));
+
+    animator.handle_events(&[GameEvent::Jumped], now);
+    assert_eq!(
+        flap.frames[0].sprite,
+        animator.birdy_sprite(&sprite_atlas, now)
+    );
+    assert_eq!(
+        "birdy",
+        animator.birdy_sprite(&sprite_atlas, now + flap.duration())
+    );
+}
+
+#[test]
+fn looping_phase() {
+    let now = Instant::now();
+    let sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    let animator = Animator::new(now);
+    let clip = &sprite_atlas.animations["coin_spin"];
+
+    assert_eq!(
+        clip.frames[0].sprite,
+        animator.looping_sprite(&sprite_atlas, "coin_spin", "coin", now, 0.0)
+    );
+    assert_eq!(
+        clip.frames[2].sprite,
+        animator.looping_sprite(&sprite_atlas, "coin_spin", "coin", now, 0.5)
+    );
+    assert_eq!(
+        "coin",
+        animator.looping_sprite(&sprite_atlas, "no_such_clip", "coin", now, 0.5)
+    );
+}
+
+#[test]
+fn spinning() {
+    let now = Instant::now();
+    let animator = Animator::new(now);
+    assert_eq!(0.0, animator.spin(now, 1.0, 0.0));
+    assert!((animator.spin(now, 1.0, 0.25) - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    let later = animator.spin(now + Duration::from_millis(500), std::f32::consts::PI, 0.0);
+    assert!((later - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+    // backwards wraps around to the other side of the turn
+    let backwards = animator.spin(now + Duration::from_secs(1), -1.0, 0.0);
+    assert!((backwards - (std::f32::consts::TAU - 1.0)).abs() < 1e-5);
+}
+
+#[test]
+fn camera_shakes_after_a_hit() {
+    let now = Instant::now();
+    let mut animator = Animator::new(now);
+    assert_eq!(Camera::IDENTITY, animator.camera(now));
+
+    animator.handle_events(&[GameEvent::Died], now);
+    let shaking = animator.camera(now + Duration::from_millis(10));
+    assert_ne!(Camera::IDENTITY, shaking);
+    assert!(shaking.position.0.abs() <= HIT_SHAKE && shaking.position.1.abs() <= HIT_SHAKE);
+    assert_eq!(Camera::IDENTITY, animator.camera(now + HIT_FLASH));
+}
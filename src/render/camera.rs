@@ -0,0 +1,92 @@
+use super::draw_list::screen_half_size;
+
+#[cfg(test)]
+mod tests;
+
+// column major, the way glsl's mat4 wants it
+pub type Matrix = [[f32; 4]; 4];
+
+pub const IDENTITY_MATRIX: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// what part of the world ends up in the playfield square on screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub position: (f32, f32), // world position shown in the middle of the playfield
+    pub zoom: f32,            // above 1.0 shows less of the world, bigger
+    pub rotation: f32,        // radians, counter-clockwise, turns the world the other way
+}
+impl Camera {
+    // world coordinates are playfield coordinates, like before there was a camera
+    pub const IDENTITY: Self = Camera {
+        position: (0.0, 0.0),
+        zoom: 1.0,
+        rotation: 0.0,
+    };
+
+    // world position to view space, where the playfield square is -1.0..1.0 on both axes
+    pub fn view_from_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (x, y) = (x - self.position.0, y - self.position.1);
+        let (sin, cos) = (-self.rotation).sin_cos();
+        (
+            (x * cos - y * sin) * self.zoom,
+            (x * sin + y * cos) * self.zoom,
+        )
+    }
+
+    pub fn world_from_view(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (x, y) = (x / self.zoom, y / self.zoom);
+        let (sin, cos) = self.rotation.sin_cos();
+        (
+            x * cos - y * sin + self.position.0,
+            x * sin + y * cos + self.position.1,
+        )
+    }
+
+    // same transform as view_from_world, for vert.glsl, leaves depth alone
+    pub fn view_matrix(&self) -> Matrix {
+        let (sin, cos) = (-self.rotation).sin_cos();
+        let (x, y) = self.position;
+        let zoom = self.zoom;
+        [
+            [cos * zoom, sin * zoom, 0.0, 0.0],
+            [-sin * zoom, cos * zoom, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [
+                -(x * cos - y * sin) * zoom,
+                -(x * sin + y * cos) * zoom,
+                0.0,
+                1.0,
+            ],
+        ]
+    }
+}
+impl Default for Camera {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// view space to normalized device coordinates, the playfield square fits the window's shorter side and screen space
+// reaches out to its edges, leaves depth alone
+pub fn projection_matrix(window_aspect_ratio: f32) -> Matrix {
+    let (half_width, half_height) = screen_half_size(window_aspect_ratio);
+    [
+        [1.0 / half_width, 0.0, 0.0, 0.0],
+        [0.0, 1.0 / half_height, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+// a 2d point through a matrix, ignoring depth
+pub fn transform(matrix: &Matrix, (x, y): (f32, f32)) -> (f32, f32) {
+    (
+        matrix[0][0] * x + matrix[1][0] * y + matrix[3][0],
+        matrix[0][1] * x + matrix[1][1] * y + matrix[3][1],
+    )
+}
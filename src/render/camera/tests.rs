@@ -0,0 +1,46 @@
+use super::*;
+
+fn assert_near((a_x, a_y): (f32, f32), (b_x, b_y): (f32, f32)) {
+    assert!(
+        (a_x - b_x).abs() < 1e-5 && (a_y - b_y).abs() < 1e-5,
+        "{:?} isn't {:?}",
+        (a_x, a_y),
+        (b_x, b_y)
+    );
+}
+
+#[test]
+fn identity() {
+    assert_eq!(IDENTITY_MATRIX, Camera::IDENTITY.view_matrix());
+    assert_eq!((0.3, -0.7), Camera::IDENTITY.view_from_world((0.3, -0.7)));
+}
+
+#[test]
+fn view() {
+    let camera = Camera {
+        position: (0.5, 0.25),
+        zoom: 2.0,
+        rotation: std::f32::consts::FRAC_PI_2,
+    };
+    // whatever the camera looks at ends up in the middle
+    assert_near((0.0, 0.0), camera.view_from_world((0.5, 0.25)));
+    // turning the camera counter-clockwise turns the world clockwise
+    assert_near((0.0, -2.0), camera.view_from_world((1.5, 0.25)));
+
+    for point in [(0.0, 0.0), (1.0, -1.0), (-0.3, 0.8)] {
+        let view = camera.view_from_world(point);
+        assert_near(view, transform(&camera.view_matrix(), point));
+        assert_near(point, camera.world_from_view(view));
+    }
+}
+
+#[test]
+fn projection() {
+    // the playfield square fills the height of a wide window
+    let wide = projection_matrix(2.0);
+    assert_near((0.5, 1.0), transform(&wide, (1.0, 1.0)));
+    assert_near((1.0, -1.0), transform(&wide, (2.0, -1.0)));
+    // and the width of a tall one
+    let tall = projection_matrix(0.5);
+    assert_near((1.0, 0.5), transform(&tall, (1.0, 1.0)));
+}
@@ -0,0 +1,488 @@
+use super::super::logic;
+use super::super::GameState;
+use super::animation::Animator;
+use super::camera::Camera;
+use super::markup;
+use super::sprite_atlas::SpriteAtlas;
+
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+pub const BIRDY_DEPTH: f32 = 0.1;
+pub const ROCK_DEPTH: f32 = 0.2;
+pub const COIN_DEPTH: f32 = 0.3;
+pub const PLAYFIELD_DEPTH: f32 = 0.9;
+pub const TEXT_DEPTH: f32 = 0.0;
+
+pub const COIN_BLOOM: f32 = 1.0;
+
+pub const WHITE: (f32, f32, f32, f32) = (1.0, 1.0, 1.0, 1.0);
+
+pub const ICON_CHARACTER: char = '\u{fffc}'; // stands in for an inline icon in a span's text
+
+pub const HUD_MARGIN: f32 = 1.0 / 40.0; // between hud text and the edges of the window
+
+pub const POPUP_HEIGHT: f32 = 1.0 / 20.0;
+pub const POPUP_GROWTH: f32 = 0.5; // how much bigger popups get by the time they're gone, relative to their height
+
+pub const BIRDY_TILT: f32 = 1.0; // radians of lean per unit of horizontal velocity
+pub const BIRDY_MAX_TILT: f32 = 0.5; // radians
+pub const ROCK_TUMBLE: f32 = std::f32::consts::TAU; // radians per second, clockwise on the way down
+
+// atlas region, top left then bottom right, in normalized texture coordinates
+pub type TextureRegion = ((f32, f32), (f32, f32));
+
+// how a sprite is turned, mirrored and stretched around its center
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub rotation: f32, // radians, counter-clockwise
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub scale: (f32, f32), // applied to half_size before rotating
+}
+impl Transform {
+    pub const IDENTITY: Self = Transform {
+        rotation: 0.0,
+        flip_x: false,
+        flip_y: false,
+        scale: (1.0, 1.0),
+    };
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub center: (f32, f32),
+    pub half_size: (f32, f32), // distance from the center to the edges, like PhysObj's width and height
+    pub depth: f32,
+    pub texture_region: TextureRegion,
+    pub tint: (f32, f32, f32, f32), // multiplied with the texture color
+    pub bloom: f32, // how brightly it glows when the bloom pass is on, 0.0 for not at all
+    pub transform: Transform,
+}
+impl Sprite {
+    // left top, right top, right bottom and left bottom corners in playfield coordinates, after the transform
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let half_width = self.half_size.0 * self.transform.scale.0;
+        let half_height = self.half_size.1 * self.transform.scale.1;
+        let (sin, cos) = self.transform.rotation.sin_cos();
+        [
+            (-half_width, half_height),
+            (half_width, half_height),
+            (half_width, -half_height),
+            (-half_width, -half_height),
+        ]
+        .map(|(x, y)| {
+            (
+                self.center.0 + x * cos - y * sin,
+                self.center.1 + x * sin + y * cos,
+            )
+        })
+    }
+
+    // texture region with the flips applied, mirrored by swapping its edges
+    pub fn flipped_texture_region(&self) -> TextureRegion {
+        let ((mut left, mut top), (mut right, mut bottom)) = self.texture_region;
+        if self.transform.flip_x {
+            std::mem::swap(&mut left, &mut right);
+        }
+        if self.transform.flip_y {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+        ((left, top), (right, bottom))
+    }
+}
+
+// a line drawn along with the text
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Decoration {
+    #[default]
+    None,
+    Underline,
+    Strikethrough,
+}
+
+// a sprite drawn in a line of text, as tall as the text above the baseline
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Icon {
+    pub texture_region: TextureRegion,
+    pub aspect_ratio: f32, // width over height
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Style {
+    pub color: (f32, f32, f32, f32), // tints icons like any other sprite
+    pub scale: f32,                  // relative to the run's normalized_height
+    pub decoration: Decoration,
+    pub icon: Option<Icon>, // every character with this style is drawn as the icon instead
+}
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: WHITE,
+            scale: 1.0,
+            decoration: Decoration::None,
+            icon: None,
+        }
+    }
+}
+
+// a piece of a text run with a single style
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: Style,
+}
+impl TextSpan {
+    pub fn new(text: String, color: (f32, f32, f32, f32)) -> Self {
+        TextSpan {
+            text,
+            style: Style {
+                color,
+                ..Style::default()
+            },
+        }
+    }
+}
+
+// which part of the text its position refers to, and how lines line up with each other
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+impl Align {
+    // how far left of its position a line of the given width starts
+    pub fn offset(self, width: f32) -> f32 {
+        match self {
+            Align::Left => 0.0,
+            Align::Center => width / 2.0,
+            Align::Right => width,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    pub align: Align,
+    pub max_width: Option<f32>, // wrap lines at spaces to stay narrower than this, in normalized units
+    pub line_spacing: f32,      // distance between the tops of lines, in line heights
+}
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout {
+            align: Align::Left,
+            max_width: None,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+// sizes and offsets are in line heights, so effects keep their proportions at any resolution
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outline {
+    pub width: f32,
+    pub color: (f32, f32, f32, f32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shadow {
+    pub offset: (f32, f32), // right and down
+    pub color: (f32, f32, f32, f32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Glow {
+    pub radius: f32,
+    pub color: (f32, f32, f32, f32),
+}
+
+// extra copies of a text run drawn behind it, to keep it readable over whatever's underneath
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TextEffects {
+    pub outline: Option<Outline>,
+    pub shadow: Option<Shadow>,
+    pub glow: Option<Glow>,
+}
+
+// dark outline and shadow for the hud, which gets drawn over everything
+pub const HUD_EFFECTS: TextEffects = TextEffects {
+    outline: Some(Outline {
+        width: 0.06,
+        color: (0.0, 0.0, 0.0, 1.0),
+    }),
+    shadow: Some(Shadow {
+        offset: (0.08, 0.1),
+        color: (0.0, 0.0, 0.0, 0.5),
+    }),
+    glow: None,
+};
+
+// how glyphs get drawn on the gpu
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextRendering {
+    // rasterized at the exact pixel size, sharpest for text that stays put
+    #[default]
+    Bitmap,
+    // from distance fields, stays sharp while it's scaled or animated
+    DistanceField,
+}
+
+// edge, corner or middle of the window that screen space text stays attached to
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+impl Anchor {
+    // which side it's on, -1.0 for left or bottom, 0.0 for the middle, 1.0 for right or top
+    fn sides(self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (-1.0, 1.0),
+            Anchor::Top => (0.0, 1.0),
+            Anchor::TopRight => (1.0, 1.0),
+            Anchor::Left => (-1.0, 0.0),
+            Anchor::Center => (0.0, 0.0),
+            Anchor::Right => (1.0, 0.0),
+            Anchor::BottomLeft => (-1.0, -1.0),
+            Anchor::Bottom => (0.0, -1.0),
+            Anchor::BottomRight => (1.0, -1.0),
+        }
+    }
+
+    // top left corner of a block of the given size attached here, margin in from the edges it's attached to, on a
+    // screen that reaches half_size out from its center
+    pub fn top_left(
+        self,
+        margin: (f32, f32),
+        size: (f32, f32),
+        half_size: (f32, f32),
+    ) -> (f32, f32) {
+        let (x, y) = self.sides();
+        (
+            x * (half_size.0 - margin.0) - (x + 1.0) / 2.0 * size.0,
+            y * (half_size.1 - margin.1) + (1.0 - y) / 2.0 * size.1,
+        )
+    }
+}
+
+// how far the window reaches from its center in screen space, which has the same units as the playfield, so the
+// shorter side fits the playfield exactly and the longer one reaches past it into the bars
+pub fn screen_half_size(window_aspect_ratio: f32) -> (f32, f32) {
+    (
+        f32::max(window_aspect_ratio, 1.0),
+        f32::max(1.0 / window_aspect_ratio, 1.0),
+    )
+}
+
+// what a text run's position is relative to
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Space {
+    // moves with the playfield and gets clipped to it, like sprites
+    #[default]
+    Playfield,
+    // stays attached to the window whatever shape it is, drawn over the playfield and bars alike
+    Screen(Anchor),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextRun {
+    // in the playfield, the top left, top center or top right corner depending on the alignment, in screen space, the
+    // margin between the text and the anchor's edges
+    pub position: (f32, f32),
+    pub space: Space,
+    pub normalized_height: f32, // height of a single line
+    pub spans: Vec<TextSpan>,
+    pub layout: TextLayout,
+    pub effects: TextEffects,
+    pub rendering: TextRendering,
+}
+
+// everything needed to draw a frame, without caring what ends up drawing it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DrawList {
+    pub camera: Camera, // looks at the sprites and playfield text, screen space text stays put
+    pub sprites: Vec<Sprite>,
+    pub text: Vec<TextRun>,
+}
+
+fn sprite_from_obj(obj: logic::PhysObj, depth: f32, texture_region: TextureRegion) -> Sprite {
+    Sprite {
+        center: (obj.x, obj.y),
+        half_size: (obj.width, obj.height),
+        depth,
+        texture_region,
+        tint: WHITE,
+        bloom: 0.0,
+        transform: Transform::IDENTITY,
+    }
+}
+
+// where in its animation an object starts, based on where it spawned so neighbours don't move in lockstep
+fn phase(obj: logic::PhysObj) -> f32 {
+    (obj.x + 1.0) / 2.0
+}
+
+pub fn build(
+    game_state: &GameState,
+    sprite_atlas: &SpriteAtlas,
+    animator: &Animator,
+    now: Instant,
+    last_frametime: Duration,
+    avg_fps: f32,
+) -> DrawList {
+    let mut draw_list = DrawList {
+        camera: animator.camera(now),
+        ..DrawList::default()
+    };
+
+    // birdy, leaning into its sideways movement
+    draw_list.sprites.push(Sprite {
+        tint: animator.birdy_tint(now),
+        transform: Transform {
+            rotation: (-game_state.birdy.x_velocity * BIRDY_TILT)
+                .clamp(-BIRDY_MAX_TILT, BIRDY_MAX_TILT),
+            ..Transform::IDENTITY
+        },
+        ..sprite_from_obj(
+            game_state.birdy,
+            BIRDY_DEPTH,
+            sprite_atlas.region(animator.birdy_sprite(sprite_atlas, now)),
+        )
+    });
+    for rock in game_state.rocks.iter() {
+        // rocks, duh, tumbling along, rising ones are the falling ones mirrored
+        let rising = rock.y_velocity.is_sign_positive();
+        let tumble = animator.spin(now, ROCK_TUMBLE, phase(*rock));
+        draw_list.sprites.push(Sprite {
+            transform: Transform {
+                rotation: if rising { tumble } else { -tumble },
+                flip_y: rising,
+                ..Transform::IDENTITY
+            },
+            ..sprite_from_obj(*rock, ROCK_DEPTH, sprite_atlas.region("rock"))
+        });
+    }
+    for coin in game_state.coins.iter() {
+        // coins, duh, spinning
+        let sprite = animator.looping_sprite(sprite_atlas, "coin_spin", "coin", now, phase(*coin));
+        draw_list.sprites.push(Sprite {
+            bloom: COIN_BLOOM,
+            ..sprite_from_obj(*coin, COIN_DEPTH, sprite_atlas.region(sprite))
+        });
+    }
+    draw_list.sprites.push(Sprite {
+        // playfield
+        center: (0.0, 0.0),
+        half_size: (1.0, 1.0),
+        depth: PLAYFIELD_DEPTH,
+        texture_region: sprite_atlas.region("playfield"),
+        tint: WHITE,
+        bloom: 0.0,
+        transform: Transform::IDENTITY,
+    });
+
+    // FPS: {avg_fps} {last_framtime}
+    let avg_fps = if avg_fps != 0.0 {
+        format!("{:.1} ", avg_fps)
+    } else {
+        "... ".to_string()
+    };
+    draw_list.text.push(TextRun {
+        position: (HUD_MARGIN, HUD_MARGIN),
+        space: Space::Screen(Anchor::TopRight),
+        normalized_height: 1.0 / 15.0,
+        spans: markup::parse(&format!(
+            "FPS: [c=#0f0]{}[/c][c=#ff0]{:?}[/c]",
+            avg_fps, last_frametime
+        ))
+        .unwrap(),
+        layout: TextLayout::default(),
+        effects: HUD_EFFECTS,
+        rendering: TextRendering::Bitmap,
+    });
+
+    // Score: {score}
+    draw_list.text.push(TextRun {
+        position: (HUD_MARGIN, HUD_MARGIN),
+        space: Space::Screen(Anchor::TopLeft),
+        normalized_height: 1.0 / 10.0,
+        spans: markup::parse_with_icons(
+            &format!("Score: [icon=coin] [c=#0f0]{}[/c]", game_state.score),
+            sprite_atlas,
+        )
+        .unwrap(),
+        layout: TextLayout::default(),
+        effects: HUD_EFFECTS,
+        rendering: TextRendering::Bitmap,
+    });
+
+    // Combo: {combo} x{multiplier}
+    if game_state.combo > 0 {
+        // right under the score
+        draw_list.text.push(TextRun {
+            position: (HUD_MARGIN, HUD_MARGIN + (1.0 / 10.0)),
+            space: Space::Screen(Anchor::TopLeft),
+            normalized_height: 1.0 / 15.0,
+            spans: markup::parse(&format!(
+                "Combo: {} [c=#ff8000]x{}[/c]",
+                game_state.combo,
+                logic::combo::multiplier(game_state.combo)
+            ))
+            .unwrap(),
+            layout: TextLayout::default(),
+            effects: HUD_EFFECTS,
+            rendering: TextRendering::Bitmap,
+        });
+    }
+
+    // +{value} popups, fading out and growing as they float away
+    for popup in game_state.score_popups.iter() {
+        let age = now
+            .saturating_duration_since(popup.spawn_time)
+            .as_secs_f32()
+            / logic::combo::POPUP_LIFETIME.as_secs_f32();
+        let alpha = f32::max(1.0 - age, 0.0);
+        draw_list.text.push(TextRun {
+            position: (popup.x, popup.y),
+            space: Space::Playfield,
+            normalized_height: POPUP_HEIGHT * (1.0 + POPUP_GROWTH * f32::min(age, 1.0)),
+            spans: vec![TextSpan::new(
+                format!("+{}", popup.value),
+                (1.0, 1.0, 0.0, alpha),
+            )],
+            // centered over where the points were scored
+            layout: TextLayout {
+                align: Align::Center,
+                ..TextLayout::default()
+            },
+            // glowing like the coins they came from
+            effects: TextEffects {
+                glow: Some(Glow {
+                    radius: 0.2,
+                    color: (1.0, 0.6, 0.0, alpha),
+                }),
+                ..TextEffects::default()
+            },
+            rendering: TextRendering::DistanceField,
+        });
+    }
+
+    draw_list
+}
@@ -0,0 +1,303 @@
+use super::*;
+
+fn sprite_atlas() -> SpriteAtlas {
+    SpriteAtlas::load("./res/atlas.json").unwrap()
+}
+
+#[test]
+fn empty_round() {
+    let now = Instant::now();
+    let game_state = GameState::new();
+    let draw_list = build(
+        &game_state,
+        &sprite_atlas(),
+        &Animator::new(now),
+        now,
+        Duration::from_millis(4),
+        0.0,
+    );
+
+    assert_eq!(
+        vec![
+            Sprite {
+                center: (0.0, 0.0),
+                half_size: (0.05, 0.05),
+                depth: BIRDY_DEPTH,
+                texture_region: ((42.0 / 64.0, 22.0 / 64.0), (50.0 / 64.0, 30.0 / 64.0)),
+                tint: WHITE,
+                bloom: 0.0,
+                transform: Transform::IDENTITY,
+            },
+            Sprite {
+                center: (0.0, 0.0),
+                half_size: (1.0, 1.0),
+                depth: PLAYFIELD_DEPTH,
+                texture_region: ((14.0 / 64.0, 42.0 / 64.0), (22.0 / 64.0, 50.0 / 64.0)),
+                tint: WHITE,
+                bloom: 0.0,
+                transform: Transform::IDENTITY,
+            },
+        ],
+        draw_list.sprites
+    );
+    assert_eq!(
+        vec![
+            TextRun {
+                position: (HUD_MARGIN, HUD_MARGIN),
+                space: Space::Screen(Anchor::TopRight),
+                normalized_height: 1.0 / 15.0,
+                spans: vec![
+                    TextSpan::new("FPS: ".to_string(), WHITE),
+                    TextSpan::new("... ".to_string(), (0.0, 1.0, 0.0, 1.0)),
+                    TextSpan::new("4ms".to_string(), (1.0, 1.0, 0.0, 1.0)),
+                ],
+                layout: TextLayout::default(),
+                effects: HUD_EFFECTS,
+                rendering: TextRendering::Bitmap,
+            },
+            TextRun {
+                position: (HUD_MARGIN, HUD_MARGIN),
+                space: Space::Screen(Anchor::TopLeft),
+                normalized_height: 1.0 / 10.0,
+                spans: vec![
+                    TextSpan::new("Score: ".to_string(), WHITE),
+                    TextSpan {
+                        text: ICON_CHARACTER.to_string(),
+                        style: Style {
+                            icon: sprite_atlas().icon("coin"),
+                            ..Style::default()
+                        },
+                    },
+                    TextSpan::new(" ".to_string(), WHITE),
+                    TextSpan::new("0".to_string(), (0.0, 1.0, 0.0, 1.0)),
+                ],
+                layout: TextLayout::default(),
+                effects: HUD_EFFECTS,
+                rendering: TextRendering::Bitmap,
+            },
+        ],
+        draw_list.text
+    );
+}
+
+#[test]
+fn rocks_face_their_direction() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    let rock = logic::PhysObj {
+        x: -1.0, // start of the tumble, upright
+        y: 0.5,
+        x_velocity: 0.0,
+        y_velocity: 0.3,
+        width: 0.1,
+        height: 0.1,
+    };
+    game_state.rocks.push(rock);
+    game_state.rocks.push(logic::PhysObj {
+        y_velocity: -0.3,
+        ..rock
+    });
+    let draw_list = build(
+        &game_state,
+        &sprite_atlas(),
+        &Animator::new(now),
+        now,
+        Duration::ZERO,
+        60.0,
+    );
+
+    let rocks: Vec<_> = draw_list
+        .sprites
+        .iter()
+        .filter(|sprite| sprite.depth == ROCK_DEPTH)
+        .collect();
+    assert_eq!(2, rocks.len());
+    assert!(rocks
+        .iter()
+        .all(|rock| rock.texture_region == sprite_atlas().region("rock")));
+    assert!(rocks[0].transform.flip_y, "rising");
+    assert!(!rocks[1].transform.flip_y, "falling");
+
+    // they tumble opposite ways, a quarter turn in a quarter second
+    let draw_list = build(
+        &game_state,
+        &sprite_atlas(),
+        &Animator::new(now),
+        now + Duration::from_millis(250),
+        Duration::ZERO,
+        60.0,
+    );
+    let rotations: Vec<f32> = draw_list
+        .sprites
+        .iter()
+        .filter(|sprite| sprite.depth == ROCK_DEPTH)
+        .map(|sprite| sprite.transform.rotation)
+        .collect();
+    assert!((rotations[0] - std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    assert!((rotations[1] + std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+}
+
+#[test]
+fn popups_fade_out() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.combo = 4;
+    game_state.score_popups.push(logic::combo::ScorePopup {
+        x: 0.2,
+        y: 0.3,
+        value: 6,
+        spawn_time: now,
+    });
+    let draw_list = build(
+        &game_state,
+        &sprite_atlas(),
+        &Animator::new(now),
+        now + logic::combo::POPUP_LIFETIME / 2,
+        Duration::ZERO,
+        60.0,
+    );
+
+    assert_eq!(4, draw_list.text.len());
+    assert_eq!("x2", draw_list.text[2].spans[1].text, "combo multiplier");
+    let popup = &draw_list.text[3];
+    assert_eq!((0.2, 0.3), popup.position);
+    assert_eq!("+6", popup.spans[0].text);
+    assert_eq!(Align::Center, popup.layout.align);
+    assert!(
+        (popup.effects.glow.unwrap().color.3 - 0.5).abs() < 0.01,
+        "glow fades too"
+    );
+    assert!((popup.spans[0].style.color.3 - 0.5).abs() < 0.01);
+    assert!(
+        (popup.normalized_height - POPUP_HEIGHT * (1.0 + POPUP_GROWTH / 2.0)).abs() < 1e-3,
+        "halfway grown"
+    );
+    assert_eq!(TextRendering::DistanceField, popup.rendering);
+}
+
+#[test]
+fn things_animate() {
+    let now = Instant::now();
+    let sprite_atlas = sprite_atlas();
+    let mut game_state = GameState::new();
+    game_state.coins.push(logic::PhysObj {
+        x: -1.0,
+        y: 0.5,
+        x_velocity: 0.0,
+        y_velocity: 0.3,
+        width: 0.1,
+        height: 0.1,
+    });
+    let mut animator = Animator::new(now);
+    animator.handle_events(&[logic::GameEvent::Jumped], now);
+
+    let later = now + Duration::from_millis(250);
+    let draw_list = build(
+        &game_state,
+        &sprite_atlas,
+        &animator,
+        later,
+        Duration::ZERO,
+        60.0,
+    );
+    assert_eq!(
+        sprite_atlas.region(animator.birdy_sprite(&sprite_atlas, later)),
+        draw_list.sprites[0].texture_region
+    );
+    assert_eq!(
+        sprite_atlas.region("coin_spin_1"),
+        draw_list.sprites[1].texture_region
+    );
+    // only coins glow
+    assert_eq!(0.0, draw_list.sprites[0].bloom);
+    assert_eq!(COIN_BLOOM, draw_list.sprites[1].bloom);
+}
+
+#[test]
+fn birdy_leans_and_flashes() {
+    let now = Instant::now();
+    let mut game_state = GameState::new();
+    game_state.birdy.x_velocity = logic::birdy::ACCEL_MOVE;
+    let mut animator = Animator::new(now);
+    animator.handle_events(&[logic::GameEvent::Died], now);
+
+    let birdy = build(
+        &game_state,
+        &sprite_atlas(),
+        &animator,
+        now,
+        Duration::ZERO,
+        60.0,
+    )
+    .sprites[0];
+    assert!(birdy.transform.rotation < 0.0, "leaning right");
+    assert!(birdy.transform.rotation >= -BIRDY_MAX_TILT);
+    assert_eq!((1.0, 0.0, 0.0, 1.0), birdy.tint);
+
+    let birdy = build(
+        &game_state,
+        &sprite_atlas(),
+        &animator,
+        now + crate::render::animation::HIT_FLASH,
+        Duration::ZERO,
+        60.0,
+    )
+    .sprites[0];
+    assert_eq!(WHITE, birdy.tint);
+}
+
+#[test]
+fn sprite_transforms() {
+    let sprite = Sprite {
+        center: (0.5, 0.5),
+        half_size: (0.2, 0.1),
+        depth: BIRDY_DEPTH,
+        texture_region: ((0.0, 0.0), (0.5, 0.25)),
+        tint: WHITE,
+        bloom: 0.0,
+        transform: Transform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            flip_x: true,
+            flip_y: false,
+            scale: (2.0, 1.0),
+        },
+    };
+
+    // a quarter turn counter-clockwise puts the left top corner at the left bottom
+    let expected = [(0.4, 0.1), (0.4, 0.9), (0.6, 0.9), (0.6, 0.1)];
+    for (corner, expected) in sprite.corners().iter().zip(expected) {
+        assert!((corner.0 - expected.0).abs() < 1e-6, "{:?}", corner);
+        assert!((corner.1 - expected.1).abs() < 1e-6, "{:?}", corner);
+    }
+    assert_eq!(((0.5, 0.0), (0.0, 0.25)), sprite.flipped_texture_region());
+}
+
+#[test]
+fn anchors() {
+    // wide window, the screen reaches past the playfield on the sides
+    let half_size = screen_half_size(2.0);
+    assert_eq!((2.0, 1.0), half_size);
+    assert_eq!((1.0, 2.0), screen_half_size(0.5));
+
+    let margin = (0.1, 0.05);
+    let size = (0.5, 0.2);
+    assert_eq!(
+        (-1.9, 0.95),
+        Anchor::TopLeft.top_left(margin, size, half_size)
+    );
+    assert_eq!(
+        (1.9 - 0.5, 0.95),
+        Anchor::TopRight.top_left(margin, size, half_size)
+    );
+    assert_eq!(
+        (1.9 - 0.5, -0.95 + 0.2),
+        Anchor::BottomRight.top_left(margin, size, half_size)
+    );
+    // centered on the axes it isn't attached to an edge of
+    assert_eq!((-0.25, 0.95), Anchor::Top.top_left(margin, size, half_size));
+    assert_eq!((-1.9, 0.1), Anchor::Left.top_left(margin, size, half_size));
+    assert_eq!(
+        (-0.25, 0.1),
+        Anchor::Center.top_left(margin, size, half_size)
+    );
+}
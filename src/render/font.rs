@@ -0,0 +1,225 @@
+use super::software::Image;
+use super::sprite_atlas::PixelRect;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+// anything text can be drawn with, strings lay out the same whichever kind it is
+#[derive(Clone, Debug)]
+pub enum Font {
+    Outline(rusttype::Font<'static>), // scalable, from a ttf or otf
+    Bitmap(BitmapFont),               // hand pixelled, from a BMFont descriptor and its page images
+}
+impl Font {
+    // BMFont descriptors by their .fnt extension, anything else as a ttf or otf
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|extension| extension == "fnt") {
+            return Ok(Font::Bitmap(BitmapFont::load(path)?));
+        }
+        let font_data: Vec<u8> = std::fs::read(path)?;
+        rusttype::Font::try_from_vec(font_data)
+            .map(Font::Outline)
+            .ok_or_else(|| format!("{} isn't a font", path.display()).into())
+    }
+
+    pub fn has_glyph(&self, character: char) -> bool {
+        match self {
+            Font::Outline(font) => font.glyph(character).id() != rusttype::GlyphId(0),
+            Font::Bitmap(font) => font.chars.contains_key(&character),
+        }
+    }
+
+    // distance from the top of a line to its baseline, for lines of the given pixel height
+    pub fn ascent(&self, height: f32) -> f32 {
+        match self {
+            Font::Outline(font) => font.v_metrics(rusttype::Scale::uniform(height)).ascent,
+            Font::Bitmap(font) => font.base * height / font.line_height,
+        }
+    }
+
+    // extra space between two characters, for lines of the given pixel height
+    pub fn kerning(&self, height: f32, first: char, second: char) -> f32 {
+        match self {
+            Font::Outline(font) => {
+                font.pair_kerning(rusttype::Scale::uniform(height), first, second)
+            }
+            Font::Bitmap(font) => {
+                font.kernings.get(&(first, second)).copied().unwrap_or(0.0) * height
+                    / font.line_height
+            }
+        }
+    }
+}
+impl From<rusttype::Font<'static>> for Font {
+    fn from(font: rusttype::Font<'static>) -> Self {
+        Font::Outline(font)
+    }
+}
+impl From<BitmapFont> for Font {
+    fn from(font: BitmapFont) -> Self {
+        Font::Bitmap(font)
+    }
+}
+
+// where a character is in a bitmap font's pages and how it sits on a line, in font pixels
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BitmapChar {
+    pub rect: PixelRect,
+    pub offset: (f32, f32), // from the pen position at the top of the line to the rect's top left corner
+    pub advance: f32,
+    pub page: usize,
+}
+
+// BMFont/AngelCode font, loaded from its text format descriptor, page images are tinted like sprites so white
+// glyphs take on whatever color the text is
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitmapFont {
+    pub line_height: f32, // what a line's pixel height corresponds to
+    pub base: f32,        // from the top of a line to the baseline
+    pub chars: HashMap<char, BitmapChar>,
+    pub kernings: HashMap<(char, char), f32>,
+    pub pages: Vec<Image>,
+}
+impl BitmapFont {
+    // pages are loaded by their file name as it's written in the descriptor
+    pub fn parse(
+        descriptor: &str,
+        mut load_page: impl FnMut(&str) -> Result<Image, Box<dyn Error>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut common = None;
+        let mut pages = Vec::new();
+        let mut chars = HashMap::new();
+        let mut kernings = HashMap::new();
+        for line in descriptor.lines() {
+            let (tag, attributes) = attributes(line)?;
+            match tag {
+                "common" => {
+                    common = Some((
+                        number(&attributes, "lineHeight", line)?,
+                        number(&attributes, "base", line)?,
+                    ))
+                }
+                "page" => {
+                    let id: usize = number(&attributes, "id", line)?;
+                    if id != pages.len() {
+                        return Err(format!("pages out of order at \"{}\"", line).into());
+                    }
+                    let file = attributes
+                        .get("file")
+                        .ok_or_else(|| format!("page without a file at \"{}\"", line))?;
+                    pages.push(load_page(file)?);
+                }
+                "char" => {
+                    let character = char::from_u32(number(&attributes, "id", line)?)
+                        .ok_or_else(|| format!("bad character id at \"{}\"", line))?;
+                    chars.insert(
+                        character,
+                        BitmapChar {
+                            rect: PixelRect {
+                                x: number(&attributes, "x", line)?,
+                                y: number(&attributes, "y", line)?,
+                                width: number(&attributes, "width", line)?,
+                                height: number(&attributes, "height", line)?,
+                            },
+                            offset: (
+                                number(&attributes, "xoffset", line)?,
+                                number(&attributes, "yoffset", line)?,
+                            ),
+                            advance: number(&attributes, "xadvance", line)?,
+                            page: number(&attributes, "page", line)?,
+                        },
+                    );
+                }
+                "kerning" => {
+                    let pair = (
+                        char::from_u32(number(&attributes, "first", line)?),
+                        char::from_u32(number(&attributes, "second", line)?),
+                    );
+                    if let (Some(first), Some(second)) = pair {
+                        kernings.insert((first, second), number(&attributes, "amount", line)?);
+                    }
+                }
+                _ => (), // info, chars and kernings counts, nothing we need
+            }
+        }
+
+        let (line_height, base): (f32, f32) =
+            common.ok_or("bitmap font descriptor has no \"common\" line")?;
+        if !(line_height.is_finite() && line_height > 0.0) {
+            return Err("bitmap font's lineHeight has to be a positive number".into());
+        }
+        for (character, bitmap_char) in chars.iter() {
+            let page = pages.get(bitmap_char.page).ok_or_else(|| {
+                format!(
+                    "'{}' is on page {}, which doesn't exist",
+                    character, bitmap_char.page
+                )
+            })?;
+            let rect = bitmap_char.rect;
+            // sums too big for a u32 don't fit either
+            let fits = |start: u32, length: u32, limit: u32| {
+                start.checked_add(length).is_some_and(|end| end <= limit)
+            };
+            if !fits(rect.x, rect.width, page.width) || !fits(rect.y, rect.height, page.height) {
+                return Err(format!("'{}' doesn't fit on its page", character).into());
+            }
+        }
+        Ok(BitmapFont {
+            line_height,
+            base,
+            chars,
+            kernings,
+            pages,
+        })
+    }
+
+    // pages live next to the descriptor
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let directory = path.parent().unwrap_or(Path::new("."));
+        BitmapFont::parse(&std::fs::read_to_string(path)?, |file| {
+            Image::load_png(directory.join(file))
+        })
+    }
+}
+
+// key=value pairs from a line of a descriptor
+type Attributes<'a> = HashMap<&'a str, &'a str>;
+
+// split a descriptor line into its tag and key=value pairs, values can be quoted to have spaces in them
+fn attributes(line: &str) -> Result<(&str, Attributes<'_>), Box<dyn Error>> {
+    let line = line.trim();
+    let (tag, mut rest) = line.split_once(' ').unwrap_or((line, ""));
+    let mut attributes = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value at \"{}\"", line))?;
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => quoted
+                .split_once('"')
+                .ok_or_else(|| format!("unterminated quote at \"{}\"", line))?,
+            None => after.split_once(' ').unwrap_or((after, "")),
+        };
+        attributes.insert(key, value);
+        rest = after;
+    }
+    Ok((tag, attributes))
+}
+
+fn number<T: FromStr>(attributes: &Attributes, key: &str, line: &str) -> Result<T, Box<dyn Error>> {
+    attributes
+        .get(key)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("missing or bad \"{}\" at \"{}\"", key, line).into())
+}
@@ -0,0 +1,106 @@
+use super::*;
+
+const DESCRIPTOR: &str = r#"info face="Tiny Pixels" size=4 bold=0 italic=0 padding=0,0,0,0 spacing=1,1
+common lineHeight=4 base=3 scaleW=8 scaleH=4 pages=1 packed=0
+page id=0 file="tiny pixels.png"
+chars count=2
+char id=65   x=0     y=0     width=2     height=3     xoffset=0     yoffset=0     xadvance=3     page=0  chnl=15
+char id=66   x=2     y=0     width=3     height=4     xoffset=1     yoffset=-1    xadvance=4     page=0  chnl=15
+kernings count=1
+kerning first=65  second=66  amount=-1
+"#;
+
+fn page() -> Image {
+    Image {
+        width: 8,
+        height: 4,
+        data: vec![255; 8 * 4 * 4],
+    }
+}
+
+#[test]
+fn parse_descriptor() {
+    let mut loaded = Vec::new();
+    let font = BitmapFont::parse(DESCRIPTOR, |file| {
+        loaded.push(file.to_string());
+        Ok(page())
+    })
+    .unwrap();
+
+    assert_eq!(vec!["tiny pixels.png".to_string()], loaded);
+    assert_eq!(4.0, font.line_height);
+    assert_eq!(3.0, font.base);
+    assert_eq!(2, font.chars.len());
+    assert_eq!(
+        BitmapChar {
+            rect: PixelRect {
+                x: 2,
+                y: 0,
+                width: 3,
+                height: 4,
+            },
+            offset: (1.0, -1.0),
+            advance: 4.0,
+            page: 0,
+        },
+        font.chars[&'B']
+    );
+    assert_eq!(Some(&-1.0), font.kernings.get(&('A', 'B')));
+
+    // scaled to the line height, like rusttype fonts
+    let font = Font::from(font);
+    assert!(font.has_glyph('A'));
+    assert!(!font.has_glyph('C'));
+    assert_eq!(6.0, font.ascent(8.0));
+    assert_eq!(-2.0, font.kerning(8.0, 'A', 'B'));
+    assert_eq!(0.0, font.kerning(8.0, 'B', 'A'));
+}
+
+#[test]
+fn bad_descriptors() {
+    let parse = |descriptor: &str| BitmapFont::parse(descriptor, |_| Ok(page()));
+    assert!(parse(DESCRIPTOR).is_ok());
+    assert!(
+        parse(&DESCRIPTOR.replace("common", "uncommon")).is_err(),
+        "no line metrics"
+    );
+    for line_height in ["0", "-4", "NaN", "inf"] {
+        assert!(
+            parse(&DESCRIPTOR.replace("lineHeight=4", &format!("lineHeight={}", line_height)))
+                .is_err(),
+            "lineHeight={}",
+            line_height
+        );
+    }
+    assert!(
+        parse(&DESCRIPTOR.replace("page=0", "page=1")).is_err(),
+        "missing page"
+    );
+    assert!(
+        parse(&DESCRIPTOR.replace("x=2", "x=6")).is_err(),
+        "off the edge of the page"
+    );
+    assert!(
+        parse(&DESCRIPTOR.replace("x=2 ", "x=4294967295 ")).is_err(),
+        "far enough off the edge to wrap around"
+    );
+    assert!(
+        parse(&DESCRIPTOR.replace("xadvance=3", "xadvance=three")).is_err(),
+        "not a number"
+    );
+    assert!(
+        parse(&DESCRIPTOR.replace("tiny pixels.png\"", "tiny pixels.png")).is_err(),
+        "unterminated quote"
+    );
+    assert!(BitmapFont::parse(DESCRIPTOR, |_| Err("no such file".into())).is_err());
+}
+
+#[test]
+fn load_by_extension() {
+    assert!(matches!(
+        Font::load("./res/Octoville.otf").unwrap(),
+        Font::Outline(_)
+    ));
+    assert!(Font::load("./res/atlas.json").is_err());
+    assert!(Font::load("./res/missing.fnt").is_err());
+}
@@ -0,0 +1,149 @@
+use super::draw_list::{Decoration, Style, TextSpan, ICON_CHARACTER};
+use super::sprite_atlas::SpriteAtlas;
+
+use std::error::Error;
+
+#[cfg(test)]
+mod tests;
+
+// split text with inline style tags into spans, e.g. "Score: [c=#0f0]42[/c]"
+//   [c=#rgb] ... [/c]     color, also #rgba, #rrggbb and #rrggbbaa
+//   [size=1.5] ... [/size] scale, relative to the rest of the run
+//   [u] ... [/u]           underline
+//   [s] ... [/s]           strikethrough
+// tags nest, and "[[" is a literal "["
+pub fn parse(markup: &str) -> Result<Vec<TextSpan>, Box<dyn Error>> {
+    parse_spans(markup, None)
+}
+
+// like parse, along with inline sprites from the atlas, which don't need closing
+//   [icon=coin]            the "coin" sprite, in the current color and size
+pub fn parse_with_icons(
+    markup: &str,
+    sprite_atlas: &SpriteAtlas,
+) -> Result<Vec<TextSpan>, Box<dyn Error>> {
+    parse_spans(markup, Some(sprite_atlas))
+}
+
+fn parse_spans(
+    markup: &str,
+    sprite_atlas: Option<&SpriteAtlas>,
+) -> Result<Vec<TextSpan>, Box<dyn Error>> {
+    let mut spans = Vec::new();
+    let mut open_tags: Vec<(&str, Style)> = Vec::new(); // along with the style from before they opened
+    let mut style = Style::default();
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        push_text(&mut spans, &rest[..start], style);
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix('[') {
+            push_text(&mut spans, "[", style);
+            rest = after;
+            continue;
+        }
+
+        let end = rest
+            .find(']')
+            .ok_or_else(|| format!("unterminated tag in \"{}\"", markup))?;
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            style = match open_tags.pop() {
+                Some((open, previous)) if open == name => previous,
+                Some((open, _)) => {
+                    return Err(format!("[/{}] closes [{}] in \"{}\"", name, open, markup).into())
+                }
+                None => {
+                    return Err(format!("[/{}] was never opened in \"{}\"", name, markup).into())
+                }
+            };
+            continue;
+        }
+
+        let (name, value) = match tag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (tag, None),
+        };
+        if let ("icon", Some(value)) = (name, value) {
+            let icon = sprite_atlas
+                .ok_or_else(|| format!("[{}] needs a sprite atlas in \"{}\"", tag, markup))?
+                .icon(value)
+                .ok_or_else(|| {
+                    format!("no sprite \"{}\" for [{}] in \"{}\"", value, tag, markup)
+                })?;
+            push_text(
+                &mut spans,
+                &ICON_CHARACTER.to_string(),
+                Style {
+                    icon: Some(icon),
+                    ..style
+                },
+            );
+            continue;
+        }
+        open_tags.push((name, style));
+        match (name, value) {
+            ("c", Some(value)) => style.color = parse_color(value)?,
+            ("size", Some(value)) => {
+                style.scale = value
+                    .parse()
+                    .ok()
+                    .filter(|scale: &f32| scale.is_finite() && *scale > 0.0)
+                    .ok_or_else(|| format!("bad size \"{}\" in \"{}\"", value, markup))?
+            }
+            ("u", None) => style.decoration = Decoration::Underline,
+            ("s", None) => style.decoration = Decoration::Strikethrough,
+            _ => return Err(format!("unknown tag [{}] in \"{}\"", tag, markup).into()),
+        }
+    }
+    push_text(&mut spans, rest, style);
+
+    if let Some((open, _)) = open_tags.last() {
+        return Err(format!("[{}] is never closed in \"{}\"", open, markup).into());
+    }
+    Ok(spans)
+}
+
+// make text show up as-is when it's put into markup
+pub fn escape(text: &str) -> String {
+    text.replace('[', "[[")
+}
+
+// add text to the last span if it has the same style, otherwise start a new one
+fn push_text(spans: &mut Vec<TextSpan>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(TextSpan {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+// "#0f0", "#0f08", "#00ff00" or "#00ff0080"
+fn parse_color(value: &str) -> Result<(f32, f32, f32, f32), Box<dyn Error>> {
+    let digits: Option<Vec<u32>> = value
+        .strip_prefix('#')
+        .and_then(|hex| hex.chars().map(|c| c.to_digit(16)).collect());
+    let channels: Vec<f32> = match digits {
+        Some(digits) if digits.len() == 3 || digits.len() == 4 => digits
+            .iter()
+            .map(|&digit| (digit * 17) as f32 / 255.0)
+            .collect(),
+        Some(digits) if digits.len() == 6 || digits.len() == 8 => digits
+            .chunks(2)
+            .map(|pair| (pair[0] * 16 + pair[1]) as f32 / 255.0)
+            .collect(),
+        _ => return Err(format!("bad color \"{}\"", value).into()),
+    };
+    Ok((
+        channels[0],
+        channels[1],
+        channels[2],
+        channels.get(3).copied().unwrap_or(1.0),
+    ))
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+use super::super::draw_list::WHITE;
+
+#[test]
+fn plain_text() {
+    assert_eq!(
+        vec![TextSpan::new("Score: 42".to_string(), WHITE)],
+        parse("Score: 42").unwrap()
+    );
+    assert!(parse("").unwrap().is_empty());
+}
+
+#[test]
+fn nested_tags() {
+    let spans = parse("Score: [c=#0f0]4[size=2][u]2[/u][/size]![/c] [s]0[/s]").unwrap();
+    let texts: Vec<_> = spans.iter().map(|span| span.text.as_str()).collect();
+    assert_eq!(vec!["Score: ", "4", "2", "!", " ", "0"], texts);
+
+    let green = (0.0, 1.0, 0.0, 1.0);
+    assert_eq!(WHITE, spans[0].style.color);
+    assert_eq!(green, spans[1].style.color);
+    assert_eq!(
+        Style {
+            color: green,
+            scale: 2.0,
+            decoration: Decoration::Underline,
+            icon: None,
+        },
+        spans[2].style
+    );
+    assert_eq!(
+        spans[1].style, spans[3].style,
+        "back to how it was before [size]"
+    );
+    assert_eq!(Decoration::Strikethrough, spans[5].style.decoration);
+}
+
+#[test]
+fn colors() {
+    let color = |markup: &str| parse(markup).unwrap()[0].style.color;
+    assert_eq!((1.0, 0.0, 0.0, 1.0), color("[c=#f00]x[/c]"));
+    assert_eq!((1.0, 0.0, 0.0, 0.0), color("[c=#f000]x[/c]"));
+    assert_eq!((0.0, 1.0, 0.0, 1.0), color("[c=#00FF00]x[/c]"));
+    let (r, g, b, a) = color("[c=#ff800080]x[/c]");
+    assert_eq!((1.0, 128.0 / 255.0, 0.0, 128.0 / 255.0), (r, g, b, a));
+}
+
+#[test]
+fn escaping() {
+    let text = "[c=#f00] isn't a tag";
+    assert_eq!(text, parse(&escape(text)).unwrap()[0].text);
+    assert_eq!("a]b", parse("a]b").unwrap()[0].text);
+}
+
+#[test]
+fn bad_markup() {
+    for markup in [
+        "[c=#f00]never closed",
+        "[/c]",
+        "[u]crossed [s]tags[/u][/s]",
+        "[c=red]x[/c]",
+        "[c=#12345]x[/c]",
+        "[size=big]x[/size]",
+        "[size=0]x[/size]",
+        "[size=-2]x[/size]",
+        "[size=NaN]x[/size]",
+        "[size=inf]x[/size]",
+        "[b]x[/b]",
+        "[u=1]x[/u]",
+        "[unterminated",
+    ] {
+        assert!(parse(markup).is_err(), "{}", markup);
+    }
+}
+
+#[test]
+fn icons() {
+    let sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    let spans =
+        parse_with_icons("[size=2][icon=coin][icon=coin][/size] x3", &sprite_atlas).unwrap();
+    assert_eq!(2, spans.len());
+    assert_eq!(
+        format!("{}{}", ICON_CHARACTER, ICON_CHARACTER),
+        spans[0].text,
+        "one character per icon"
+    );
+    assert_eq!(sprite_atlas.icon("coin"), spans[0].style.icon);
+    assert_eq!(2.0, spans[0].style.scale);
+    assert_eq!(None, spans[1].style.icon, "icons don't need closing");
+
+    assert!(parse_with_icons("[icon=nope]", &sprite_atlas).is_err());
+    assert!(parse("[icon=coin]").is_err(), "no atlas to get it from");
+}
@@ -0,0 +1,289 @@
+use super::software::Image;
+use super::{Corner, UNIT_QUAD};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use glium::uniforms::{AsUniformValue, UniformValue, Uniforms};
+use glium::Surface;
+use serde::Deserialize;
+
+#[cfg(test)]
+mod tests;
+
+pub const VERTEX_SHADER: &str = "post_vert.glsl"; // shared by every pass, lives next to the settings
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+// one full-screen pass, its fragment shader gets the frame so far as `frame`, its size in pixels as `frame_size`,
+// whatever glows as `bloom_mask`, and the lookup table and parameters as uniforms of the same names
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct PassSettings {
+    pub name: String,
+    pub shader: String, // relative to the settings file
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub parameters: HashMap<String, f32>,
+    #[serde(default)]
+    pub lut: Option<String>, // color lookup table image, relative to the settings file too
+}
+
+// full-screen passes run over every frame, in order, lives in res as json
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Settings {
+    pub passes: Vec<PassSettings>,
+}
+impl Settings {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let settings: Settings = serde_json::from_str(json)?;
+        for (i, pass) in settings.passes.iter().enumerate() {
+            if settings.passes[..i]
+                .iter()
+                .any(|other| other.name == pass.name)
+            {
+                return Err(format!("there's more than one \"{}\" pass", pass.name).into());
+            }
+        }
+        Ok(settings)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Settings::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    // turn on just the named passes, whatever the settings said, keeping their order
+    pub fn enable_only(&mut self, names: &[&str]) -> Result<(), Box<dyn Error>> {
+        if let Some(name) = names
+            .iter()
+            .find(|name| !self.passes.iter().any(|pass| pass.name == **name))
+        {
+            return Err(format!("there's no \"{}\" pass", name).into());
+        }
+        for pass in self.passes.iter_mut() {
+            pass.enabled = names.contains(&pass.name.as_str());
+        }
+        Ok(())
+    }
+}
+
+struct Pass {
+    program: glium::program::Program,
+    parameters: HashMap<String, f32>,
+    lut: Option<glium::texture::Texture2d>,
+}
+
+// everything a pass's shader gets, parameters are only known once the settings are loaded
+struct PassUniforms<'a> {
+    frame: glium::uniforms::Sampler<'a, glium::texture::srgb_texture2d::SrgbTexture2d>,
+    frame_size: (u32, u32),
+    bloom_mask: glium::uniforms::Sampler<'a, glium::texture::srgb_texture2d::SrgbTexture2d>,
+    lut: Option<glium::uniforms::Sampler<'a, glium::texture::Texture2d>>,
+    parameters: &'a HashMap<String, f32>,
+}
+impl Uniforms for PassUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut output: F) {
+        output("frame", self.frame.as_uniform_value());
+        output(
+            "frame_size",
+            UniformValue::Vec2([self.frame_size.0 as f32, self.frame_size.1 as f32]),
+        );
+        output("bloom_mask", self.bloom_mask.as_uniform_value());
+        if let Some(lut) = self.lut.as_ref() {
+            output("lut", lut.as_uniform_value());
+        }
+        for (name, value) in self.parameters.iter() {
+            output(name, UniformValue::Float(*value));
+        }
+    }
+}
+
+// offscreen frames as big as the window, the scene gets drawn to one and the passes take turns with the other two
+struct Targets {
+    size: (u32, u32),
+    scene: glium::texture::srgb_texture2d::SrgbTexture2d,
+    depth: glium::framebuffer::DepthRenderBuffer, // shared by the scene and the bloom mask, one's done before the other
+    bloom_mask: glium::texture::srgb_texture2d::SrgbTexture2d,
+    ping_pong: [glium::texture::srgb_texture2d::SrgbTexture2d; 2],
+}
+impl Targets {
+    fn new(disp: &glium::Display, (width, height): (u32, u32)) -> Result<Self, Box<dyn Error>> {
+        let frame = || glium::texture::srgb_texture2d::SrgbTexture2d::empty(disp, width, height);
+        Ok(Targets {
+            size: (width, height),
+            scene: frame()?,
+            depth: glium::framebuffer::DepthRenderBuffer::new(
+                disp,
+                glium::texture::DepthFormat::I24,
+                width,
+                height,
+            )?,
+            bloom_mask: frame()?,
+            ping_pong: [frame()?, frame()?],
+        })
+    }
+}
+
+// the enabled passes, ready to run between drawing a frame and showing it
+pub struct PostProcessor {
+    unit_quad: glium::VertexBuffer<Corner>,
+    passes: Vec<Pass>,
+    targets: Option<Targets>, // made once the window's size is known
+}
+impl PostProcessor {
+    // shaders and lookup tables are loaded from the given directory, disabled passes aren't loaded at all
+    pub fn new(
+        disp: &glium::Display,
+        settings: &Settings,
+        directory: impl AsRef<Path>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let directory = directory.as_ref();
+        let vertex_shader = std::fs::read_to_string(directory.join(VERTEX_SHADER))?;
+        let mut passes = Vec::new();
+        for pass in settings.passes.iter().filter(|pass| pass.enabled) {
+            let program = glium::program::Program::from_source(
+                disp,
+                &vertex_shader,
+                &std::fs::read_to_string(directory.join(&pass.shader))?,
+                None,
+            )
+            .map_err(|e| format!("couldn't build the \"{}\" pass: {}", pass.name, e))?;
+            let lut = match pass.lut.as_ref() {
+                Some(lut) => {
+                    // not srgb, the shader looks colors up the way they'd be stored
+                    let image = Image::load_png(directory.join(lut))?;
+                    Some(glium::texture::Texture2d::new(
+                        disp,
+                        glium::texture::RawImage2d {
+                            data: std::borrow::Cow::from(&image.data),
+                            width: image.width,
+                            height: image.height,
+                            format: glium::texture::ClientFormat::U8U8U8U8,
+                        },
+                    )?)
+                }
+                None => None,
+            };
+            passes.push(Pass {
+                program,
+                parameters: pass.parameters.clone(),
+                lut,
+            });
+        }
+        Ok(PostProcessor {
+            unit_quad: glium::VertexBuffer::new(disp, &UNIT_QUAD)?,
+            passes,
+            targets: None,
+        })
+    }
+
+    // with nothing enabled frames go straight to the window
+    pub fn is_active(&self) -> bool {
+        !self.passes.is_empty()
+    }
+
+    // only worth drawing the bloom mask when a pass samples it
+    pub fn wants_bloom_mask(&self) -> bool {
+        self.passes
+            .iter()
+            .any(|pass| pass.program.get_uniform("bloom_mask").is_some())
+    }
+
+    // keep the offscreen frames as big as the window
+    pub fn resize(
+        &mut self,
+        disp: &glium::Display,
+        size: (u32, u32),
+    ) -> Result<(), Box<dyn Error>> {
+        if self.targets.as_ref().map(|targets| targets.size) != Some(size) {
+            self.targets = Some(Targets::new(disp, size)?);
+        }
+        Ok(())
+    }
+
+    fn targets(&self) -> &Targets {
+        self.targets
+            .as_ref()
+            .expect("post processor used before it was sized")
+    }
+
+    // where the frame gets drawn instead of the window
+    pub fn scene<'a>(&'a self, disp: &glium::Display) -> glium::framebuffer::SimpleFrameBuffer<'a> {
+        let targets = self.targets();
+        glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+            disp,
+            &targets.scene,
+            &targets.depth,
+        )
+        .unwrap()
+    }
+
+    // where whatever glows gets drawn, in its own color over black, lined up with the scene
+    pub fn bloom_mask<'a>(
+        &'a self,
+        disp: &glium::Display,
+    ) -> glium::framebuffer::SimpleFrameBuffer<'a> {
+        let targets = self.targets();
+        glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+            disp,
+            &targets.bloom_mask,
+            &targets.depth,
+        )
+        .unwrap()
+    }
+
+    // run every pass over the scene, the last one draws to the window
+    pub fn run(&self, disp: &glium::Display, f_buff: &mut impl Surface) {
+        let targets = self.targets();
+        let mut input = &targets.scene;
+        for (i, pass) in self.passes.iter().enumerate() {
+            if i + 1 == self.passes.len() {
+                self.draw_pass(f_buff, pass, input);
+            } else {
+                let output = &targets.ping_pong[i % 2];
+                let mut framebuffer =
+                    glium::framebuffer::SimpleFrameBuffer::new(disp, output).unwrap();
+                self.draw_pass(&mut framebuffer, pass, input);
+                input = output;
+            }
+        }
+    }
+
+    fn draw_pass(
+        &self,
+        surface: &mut impl Surface,
+        pass: &Pass,
+        input: &glium::texture::srgb_texture2d::SrgbTexture2d,
+    ) {
+        let targets = self.targets();
+        let clamped = |texture| {
+            glium::uniforms::Sampler::new(texture)
+                .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+        };
+        surface
+            .draw(
+                &self.unit_quad,
+                glium::index::NoIndices(glium::index::PrimitiveType::TriangleStrip),
+                &pass.program,
+                &PassUniforms {
+                    frame: clamped(input),
+                    frame_size: targets.size,
+                    bloom_mask: clamped(&targets.bloom_mask),
+                    lut: pass.lut.as_ref().map(|lut| {
+                        glium::uniforms::Sampler::new(lut)
+                            .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
+                            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+                            .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                    }),
+                    parameters: &pass.parameters,
+                },
+                &glium::DrawParameters::default(),
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use super::*;
+
+#[test]
+fn settings() {
+    let settings = Settings::load("./res/post_process.json").unwrap();
+    let names: Vec<&str> = settings
+        .passes
+        .iter()
+        .map(|pass| pass.name.as_str())
+        .collect();
+    assert_eq!(vec!["bloom", "grade", "vignette", "crt"], names);
+    // everything they need is there
+    assert!(Path::new("./res").join(VERTEX_SHADER).exists());
+    for pass in settings.passes.iter() {
+        assert!(
+            Path::new("./res").join(&pass.shader).exists(),
+            "{}",
+            pass.name
+        );
+        if let Some(lut) = pass.lut.as_ref() {
+            Image::load_png(Path::new("./res").join(lut)).unwrap();
+        }
+    }
+}
+
+#[test]
+fn defaults() {
+    let settings =
+        Settings::from_json(r#"{ "passes": [{ "name": "crt", "shader": "crt.glsl" }] }"#).unwrap();
+    assert_eq!(
+        PassSettings {
+            name: "crt".to_string(),
+            shader: "crt.glsl".to_string(),
+            enabled: true,
+            parameters: HashMap::new(),
+            lut: None,
+        },
+        settings.passes[0]
+    );
+}
+
+#[test]
+fn bad_settings() {
+    assert!(Settings::from_json(
+        r#"{ "passes": [{ "name": "crt", "shader": "a.glsl" }, { "name": "crt", "shader": "b.glsl" }] }"#
+    )
+    .is_err());
+    assert!(Settings::from_json(r#"{ "passes": [{ "name": "crt" }] }"#).is_err());
+}
+
+#[test]
+fn toggling() {
+    let mut settings = Settings::load("./res/post_process.json").unwrap();
+    settings.enable_only(&["crt", "bloom"]).unwrap();
+    let enabled: Vec<&str> = settings
+        .passes
+        .iter()
+        .filter(|pass| pass.enabled)
+        .map(|pass| pass.name.as_str())
+        .collect();
+    assert_eq!(vec!["bloom", "crt"], enabled, "in the settings' order");
+
+    assert!(settings.enable_only(&["sepia"]).is_err());
+    settings.enable_only(&[]).unwrap();
+    assert!(settings.passes.iter().all(|pass| !pass.enabled));
+}
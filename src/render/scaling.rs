@@ -0,0 +1,69 @@
+use super::camera::Matrix;
+
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+pub const PLAYFIELD_RESOLUTION: u32 = 160; // atlas texels across the playfield, the birdy's 8 are a twentieth of it
+
+// how the playfield gets from the draw list onto the window, screen space text is always drawn at full resolution
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    // straight to the window, texels come out whatever size the window makes them
+    #[default]
+    Native,
+    // at PLAYFIELD_RESOLUTION, then blown up by the biggest whole number that fits so every texel is the same size
+    PixelPerfect,
+    // at PLAYFIELD_RESOLUTION, then stretched to fit and filtered so uneven texels blur instead of jumping around
+    Smooth,
+}
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "native" => Ok(Scaling::Native),
+            "pixel-perfect" => Ok(Scaling::PixelPerfect),
+            "smooth" => Ok(Scaling::Smooth),
+            _ => Err(format!(
+                "unknown scaling \"{}\", expected native, pixel-perfect or smooth",
+                name
+            )),
+        }
+    }
+}
+
+// where the playfield square goes on a window of the given size, centered with bars around whatever's left over
+pub fn playfield_rect(scaling: Scaling, (width, height): (u32, u32)) -> glium::Rect {
+    let fits = u32::min(width, height);
+    let side = match scaling {
+        Scaling::PixelPerfect if fits >= PLAYFIELD_RESOLUTION => {
+            fits / PLAYFIELD_RESOLUTION * PLAYFIELD_RESOLUTION
+        }
+        // windows too small to fit it even once get it shrunk
+        Scaling::Native | Scaling::PixelPerfect | Scaling::Smooth => fits,
+    };
+    glium::Rect {
+        left: (width - side) / 2,
+        bottom: (height - side) / 2,
+        width: side,
+        height: side,
+    }
+}
+
+// view space to normalized device coordinates on a surface of the given size, putting the playfield square at rect
+pub fn projection_onto(rect: glium::Rect, (width, height): (u32, u32)) -> Matrix {
+    let (width, height) = (width as f32, height as f32);
+    [
+        [rect.width as f32 / width, 0.0, 0.0, 0.0],
+        [0.0, rect.height as f32 / height, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [
+            (2 * rect.left + rect.width) as f32 / width - 1.0,
+            (2 * rect.bottom + rect.height) as f32 / height - 1.0,
+            0.0,
+            1.0,
+        ],
+    ]
+}
@@ -0,0 +1,75 @@
+use super::*;
+
+#[test]
+fn parse() {
+    assert_eq!(Ok(Scaling::Native), "native".parse());
+    assert_eq!(Ok(Scaling::PixelPerfect), "pixel-perfect".parse());
+    assert_eq!(Ok(Scaling::Smooth), "smooth".parse());
+    assert!("blurry".parse::<Scaling>().is_err());
+}
+
+#[test]
+fn playfield_rects() {
+    // 768 fits 160 four times, with 64 pixels left over split above and below
+    assert_eq!(
+        glium::Rect {
+            left: 192,
+            bottom: 64,
+            width: 640,
+            height: 640,
+        },
+        playfield_rect(Scaling::PixelPerfect, (1024, 768))
+    );
+    assert_eq!(
+        glium::Rect {
+            left: 128,
+            bottom: 0,
+            width: 768,
+            height: 768,
+        },
+        playfield_rect(Scaling::Smooth, (1024, 768))
+    );
+    // tall windows get bars above and below
+    assert_eq!(
+        glium::Rect {
+            left: 20,
+            bottom: 220,
+            width: 160,
+            height: 160,
+        },
+        playfield_rect(Scaling::PixelPerfect, (200, 600))
+    );
+    // too small for even one whole texel per pixel
+    assert_eq!(
+        glium::Rect {
+            left: 0,
+            bottom: 10,
+            width: 100,
+            height: 100,
+        },
+        playfield_rect(Scaling::PixelPerfect, (100, 120))
+    );
+}
+
+#[test]
+fn projections() {
+    // the whole playfield square fits the shorter side, like the window's own projection
+    let size = (1024, 768);
+    assert_eq!(
+        super::super::camera::projection_matrix(1024.0 / 768.0),
+        projection_onto(playfield_rect(Scaling::Native, size), size)
+    );
+    // the corners of the playfield square land on the corners of the rect
+    let rect = playfield_rect(Scaling::PixelPerfect, size);
+    let projection = projection_onto(rect, size);
+    let to_pixels = |(x, y): (f32, f32)| ((x + 1.0) / 2.0 * 1024.0, (y + 1.0) / 2.0 * 768.0);
+    for (corner, pixels) in [((-1.0, -1.0), (192.0, 64.0)), ((1.0, 1.0), (832.0, 704.0))] {
+        let (x, y) = to_pixels(super::super::camera::transform(&projection, corner));
+        assert!(
+            (x - pixels.0).abs() < 1e-3 && (y - pixels.1).abs() < 1e-3,
+            "{:?} isn't {:?}",
+            (x, y),
+            pixels
+        );
+    }
+}
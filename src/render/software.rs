@@ -0,0 +1,336 @@
+use super::camera::{self, Camera};
+use super::draw_list::{screen_half_size, DrawList, Space, TextRun, TextureRegion};
+use super::text;
+
+use std::error::Error;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests;
+
+// 8 bit rgba image, top row first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+impl Image {
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let decoder = png::Decoder::new(std::fs::File::open(path)?);
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let image_info = reader.next_frame(&mut data)?;
+        if image_info.color_type != png::ColorType::Rgba
+            || image_info.bit_depth != png::BitDepth::Eight
+        {
+            return Err("expected an 8 bit rgba png".into());
+        }
+        data.truncate(image_info.buffer_size());
+        Ok(Image {
+            width: image_info.width,
+            height: image_info.height,
+            data,
+        })
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)?;
+        Ok(())
+    }
+}
+
+// floating point rgba texture, sampled with nearest filtering like the glium path
+struct Texture {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+impl Texture {
+    fn from_image(image: &Image) -> Self {
+        Texture {
+            width: image.width as usize,
+            height: image.height as usize,
+            data: image.data.iter().map(|&c| c as f32 / 255.0).collect(),
+        }
+    }
+
+    fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        let index = (x + y * self.width) * 4;
+        [
+            self.data[index],
+            self.data[index + 1],
+            self.data[index + 2],
+            self.data[index + 3],
+        ]
+    }
+}
+
+struct Target {
+    width: usize,
+    height: usize,
+    aspect_ratio: f32,
+    camera: Camera, // like vert.glsl's view uniform, identity for screen space text
+    clip_to_playfield: bool, // like frag.glsl's uniform, off for screen space text
+    color: Vec<f32>,
+    depth: Vec<f32>,
+}
+impl Target {
+    // same transform as vert.glsl, world position to normalized device coordinates
+    fn device_from_world(&self, position: (f32, f32)) -> (f32, f32) {
+        camera::transform(
+            &camera::projection_matrix(self.aspect_ratio),
+            self.camera.view_from_world(position),
+        )
+    }
+
+    fn view_from_device(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (half_width, half_height) = screen_half_size(self.aspect_ratio);
+        (x * half_width, y * half_height)
+    }
+
+    // rasterize a quad given by its left top, right top, right bottom and left bottom corners in playfield
+    // coordinates, any rotation is fine as long as it's still a parallelogram
+    fn draw_quad(
+        &mut self,
+        texture: &Texture,
+        [lt, rt, _, lb]: [(f32, f32); 4],
+        depth: f32,
+        texture_region: TextureRegion,
+        tint: (f32, f32, f32, f32),
+    ) {
+        // edges running along the texture's u and v axes
+        let across = (rt.0 - lt.0, rt.1 - lt.1);
+        let down = (lb.0 - lt.0, lb.1 - lt.1);
+        let determinant = across.0 * down.1 - across.1 * down.0;
+        if determinant == 0.0 || texture.width == 0 || texture.height == 0 {
+            return;
+        }
+
+        // pixel bounds of the quad
+        let corners = [lt, rt, (rt.0 + down.0, rt.1 + down.1), lb]
+            .map(|corner| self.device_from_world(corner));
+        let device_left = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min);
+        let device_right = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max);
+        let device_top = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max);
+        let device_bottom = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min);
+        let to_column =
+            |x: f32| ((x + 1.0) / 2.0 * self.width as f32).clamp(0.0, self.width as f32) as usize;
+        let to_row =
+            |y: f32| ((1.0 - y) / 2.0 * self.height as f32).clamp(0.0, self.height as f32) as usize;
+
+        for row in to_row(device_top)..(to_row(device_bottom) + 1).min(self.height) {
+            for column in to_column(device_left)..(to_column(device_right) + 1).min(self.width) {
+                // sample at the pixel center
+                let device_x = (column as f32 + 0.5) / self.width as f32 * 2.0 - 1.0;
+                let device_y = 1.0 - (row as f32 + 0.5) / self.height as f32 * 2.0;
+                let (view_x, view_y) = self.view_from_device((device_x, device_y));
+                // frag.glsl keeps everything but screen space text inside the playfield
+                if self.clip_to_playfield
+                    && (!(-1.0..=1.0).contains(&view_x) || !(-1.0..=1.0).contains(&view_y))
+                {
+                    continue;
+                }
+                let (x, y) = self.camera.world_from_view((view_x, view_y));
+
+                // how far along each edge the pixel is, 0.0..1.0 means it's inside the quad
+                let offset = (x - lt.0, y - lt.1);
+                let s = (offset.0 * down.1 - offset.1 * down.0) / determinant;
+                let t = (across.0 * offset.1 - across.1 * offset.0) / determinant;
+                if !(0.0..1.0).contains(&s) || !(0.0..1.0).contains(&t) {
+                    continue;
+                }
+
+                let u = texture_region.0 .0 + s * (texture_region.1 .0 - texture_region.0 .0);
+                let v = texture_region.0 .1 + t * (texture_region.1 .1 - texture_region.0 .1);
+                let texel = texture.sample(u, v);
+                let source = [
+                    texel[0] * tint.0,
+                    texel[1] * tint.1,
+                    texel[2] * tint.2,
+                    texel[3] * tint.3,
+                ];
+                if source[3] == 0.0 {
+                    continue;
+                }
+
+                let index = column + row * self.width;
+                if depth >= self.depth[index] {
+                    continue;
+                }
+                self.depth[index] = depth;
+
+                // standard alpha blending
+                let alpha = source[3];
+                for (channel, source) in self.color[index * 4..index * 4 + 4].iter_mut().zip(source)
+                {
+                    *channel = source * alpha + *channel * (1.0 - alpha);
+                }
+            }
+        }
+    }
+}
+
+// distance field runs too, text gets rasterized at the size it's drawn at here anyway
+fn draw_text_run(
+    target: &mut Target,
+    texture_atlas: &Texture,
+    text_run: &TextRun,
+    fonts: &[super::font::Font],
+) {
+    let text = super::super_string_from_run(text_run, fonts);
+    let line_height = text::pixel_height(&text, target.height as u32);
+    let (data, text_width, text_height) = text::rasterize(&text, line_height);
+    let texture = Texture {
+        width: text_width,
+        height: text_height,
+        data,
+    };
+    if texture.height == 0 {
+        return;
+    }
+    // texture pixels to normalized units
+    let unit = text_run.normalized_height / line_height.ceil();
+    // the texture has room for effects all around the text, which lines up as if it weren't there
+    let margin = text::margin(&text, line_height) as f32 * unit;
+    let (position_x, top) = super::position_of_run(text_run, &text, target.aspect_ratio);
+    let top = top + margin;
+    let left = position_x
+        - margin
+        - text_run
+            .layout
+            .align
+            .offset(text_width as f32 * unit - margin * 2.0);
+    let right = left + text_width as f32 * unit;
+    let bottom = top - text_height as f32 * unit;
+    target.draw_quad(
+        &texture,
+        [(left, top), (right, top), (right, bottom), (left, bottom)],
+        super::draw_list::TEXT_DEPTH,
+        ((0.0, 0.0), (1.0, 1.0)),
+        super::draw_list::WHITE,
+    );
+    for icon in text::positioned_icons(&text, line_height, (left + margin, top - margin), unit) {
+        target.draw_quad(
+            texture_atlas,
+            [
+                (icon.left, icon.top),
+                (icon.right, icon.top),
+                (icon.right, icon.bottom),
+                (icon.left, icon.bottom),
+            ],
+            icon.depth,
+            icon.texture_region,
+            icon.color,
+        );
+    }
+}
+
+// draw a frame on the CPU, following the same rules as the glium path
+pub fn draw(
+    draw_list: &DrawList,
+    texture_atlas: &Image,
+    fonts: &[super::font::Font],
+    width: u32,
+    height: u32,
+) -> Image {
+    let mut target = Target {
+        width: width as usize,
+        height: height as usize,
+        aspect_ratio: width as f32 / height as f32,
+        camera: draw_list.camera,
+        clip_to_playfield: true,
+        color: vec![0.0; width as usize * height as usize * 4],
+        depth: vec![f32::MAX; width as usize * height as usize],
+    };
+
+    let texture_atlas = Texture::from_image(texture_atlas);
+    for sprite in draw_list.sprites.iter() {
+        target.draw_quad(
+            &texture_atlas,
+            sprite.corners(),
+            sprite.depth,
+            sprite.flipped_texture_region(),
+            sprite.tint,
+        );
+    }
+
+    let (playfield_runs, screen_runs): (Vec<_>, Vec<_>) = draw_list
+        .text
+        .iter()
+        .partition(|text_run| text_run.space == Space::Playfield);
+    for text_run in playfield_runs {
+        draw_text_run(&mut target, &texture_atlas, text_run, fonts);
+    }
+    // screen space text goes on top of everything, bars included
+    target.depth.fill(f32::MAX);
+    target.camera = Camera::IDENTITY;
+    target.clip_to_playfield = false;
+    for text_run in screen_runs {
+        draw_text_run(&mut target, &texture_atlas, text_run, fonts);
+    }
+
+    Image {
+        width,
+        height,
+        data: target
+            .color
+            .iter()
+            .map(|&c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect(),
+    }
+}
+
+// compare against a golden image, set FLAP_BLESS to (re)write it instead
+pub fn compare_to_golden(
+    image: &Image,
+    path: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<(), String> {
+    let path = path.as_ref();
+    if std::env::var_os("FLAP_BLESS").is_some() {
+        return image.save_png(path).map_err(|e| e.to_string());
+    }
+
+    let golden = Image::load_png(path).map_err(|e| {
+        format!(
+            "couldn't load {}: {} (set FLAP_BLESS to create it)",
+            path.display(),
+            e
+        )
+    })?;
+    if (golden.width, golden.height) != (image.width, image.height) {
+        return Err(format!(
+            "{} is {}x{}, rendered image is {}x{}",
+            path.display(),
+            golden.width,
+            golden.height,
+            image.width,
+            image.height
+        ));
+    }
+    let mismatched = golden
+        .data
+        .chunks(4)
+        .zip(image.data.chunks(4))
+        .filter(|(a, b)| {
+            a.iter()
+                .zip(b.iter())
+                .any(|(a, b)| a.abs_diff(*b) > tolerance)
+        })
+        .count();
+    if mismatched > 0 {
+        return Err(format!(
+            "{} pixels differ from {}",
+            mismatched,
+            path.display()
+        ));
+    }
+    Ok(())
+}
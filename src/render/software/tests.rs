@@ -0,0 +1,185 @@
+use super::*;
+
+use super::super::animation::Animator;
+use super::super::draw_list;
+use super::super::font::Font;
+use super::super::sprite_atlas::SpriteAtlas;
+use crate::logic::PhysObj;
+use crate::GameState;
+
+use std::time::{Duration, Instant};
+
+fn font() -> Font {
+    Font::load("./res/Octoville.otf").unwrap()
+}
+
+fn scene() -> DrawList {
+    let now = Instant::now();
+    let mut game_state = GameState::with_seed(0);
+    game_state.birdy.x = -0.3;
+    game_state.rocks.push(PhysObj {
+        x: 0.4,
+        y: 0.5,
+        x_velocity: 0.0,
+        y_velocity: -0.3,
+        width: 0.15,
+        height: 0.15,
+    });
+    game_state.coins.push(PhysObj {
+        x: -0.5,
+        y: -0.6,
+        x_velocity: 0.0,
+        y_velocity: 0.8,
+        width: 0.1,
+        height: 0.1,
+    });
+    game_state.score = 42;
+    draw_list::build(
+        &game_state,
+        &SpriteAtlas::load("./res/atlas.json").unwrap(),
+        &Animator::new(now),
+        now,
+        Duration::from_millis(8),
+        120.0,
+    )
+}
+
+#[test]
+fn png_round_trip() {
+    let image = Image {
+        width: 2,
+        height: 1,
+        data: vec![255, 0, 0, 255, 0, 0, 255, 128],
+    };
+    let path = std::env::temp_dir().join("flap_png_round_trip.png");
+    image.save_png(&path).unwrap();
+    assert_eq!(image, Image::load_png(&path).unwrap());
+}
+
+#[test]
+fn letterboxing() {
+    let texture_atlas = Image::load_png("./res/atlas.png").unwrap();
+    let image = draw(&DrawList::default(), &texture_atlas, &[font()], 8, 4);
+    assert!(image.data.iter().all(|&c| c == 0));
+
+    // the playfield only covers the middle square of a wide frame
+    let draw_list = DrawList {
+        sprites: vec![draw_list::Sprite {
+            center: (0.0, 0.0),
+            half_size: (1.0, 1.0),
+            depth: draw_list::PLAYFIELD_DEPTH,
+            texture_region: SpriteAtlas::load("./res/atlas.json")
+                .unwrap()
+                .region("playfield"),
+            tint: draw_list::WHITE,
+            bloom: 0.0,
+            transform: draw_list::Transform::IDENTITY,
+        }],
+        ..DrawList::default()
+    };
+    let image = draw(&draw_list, &texture_atlas, &[font()], 8, 4);
+    let alpha = |x: usize, y: usize| image.data[(x + y * 8) * 4 + 3];
+    assert_eq!(0, alpha(0, 0));
+    assert_eq!(0, alpha(7, 3));
+    assert_eq!(255, alpha(2, 0));
+    assert_eq!(255, alpha(5, 3));
+}
+
+#[test]
+fn camera() {
+    // 2x1 texture, red on the left and blue on the right
+    let texture_atlas = Image {
+        width: 2,
+        height: 1,
+        data: vec![255, 0, 0, 255, 0, 0, 255, 255],
+    };
+    let draw_list = DrawList {
+        camera: Camera {
+            position: (-0.5, 0.0),
+            ..Camera::IDENTITY
+        },
+        sprites: vec![draw_list::Sprite {
+            center: (0.0, 0.0),
+            half_size: (1.0, 0.5),
+            depth: 0.5,
+            texture_region: ((0.0, 0.0), (1.0, 1.0)),
+            tint: draw_list::WHITE,
+            bloom: 0.0,
+            transform: draw_list::Transform::IDENTITY,
+        }],
+        ..DrawList::default()
+    };
+    let image = draw(&draw_list, &texture_atlas, &[font()], 4, 4);
+    let pixel = |x: usize, y: usize| image.data[(x + y * 4) * 4..(x + y * 4) * 4 + 4].to_vec();
+
+    // looking left of the middle, the red half moves over to where the blue one was
+    assert_eq!(vec![0, 0, 0, 0], pixel(0, 1), "past the sprite's left edge");
+    assert_eq!(vec![255, 0, 0, 255], pixel(1, 1));
+    assert_eq!(vec![255, 0, 0, 255], pixel(2, 1));
+    assert_eq!(vec![0, 0, 255, 255], pixel(3, 1));
+}
+
+#[test]
+fn transformed_sprites() {
+    // 2x1 texture, red on the left and blue on the right
+    let texture_atlas = Image {
+        width: 2,
+        height: 1,
+        data: vec![255, 0, 0, 255, 0, 0, 255, 255],
+    };
+    let sprite = draw_list::Sprite {
+        center: (0.0, 0.0),
+        half_size: (1.0, 0.5),
+        depth: 0.5,
+        texture_region: ((0.0, 0.0), (1.0, 1.0)),
+        tint: draw_list::WHITE,
+        bloom: 0.0,
+        transform: draw_list::Transform::IDENTITY,
+    };
+    let pixel = |sprite: draw_list::Sprite, x: usize, y: usize| {
+        let draw_list = DrawList {
+            sprites: vec![sprite],
+            ..DrawList::default()
+        };
+        let image = draw(&draw_list, &texture_atlas, &[font()], 4, 4);
+        let index = (x + y * 4) * 4;
+        image.data[index..index + 4].to_vec()
+    };
+
+    assert_eq!(vec![255, 0, 0, 255], pixel(sprite, 0, 1));
+    assert_eq!(vec![0, 0, 0, 0], pixel(sprite, 0, 0), "outside the quad");
+
+    let flipped = draw_list::Sprite {
+        transform: draw_list::Transform {
+            flip_x: true,
+            ..draw_list::Transform::IDENTITY
+        },
+        ..sprite
+    };
+    assert_eq!(vec![0, 0, 255, 255], pixel(flipped, 0, 1));
+
+    // a quarter turn counter-clockwise moves the left half to the bottom
+    let rotated = draw_list::Sprite {
+        transform: draw_list::Transform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            ..draw_list::Transform::IDENTITY
+        },
+        ..sprite
+    };
+    assert_eq!(vec![255, 0, 0, 255], pixel(rotated, 1, 3));
+    assert_eq!(vec![0, 0, 255, 255], pixel(rotated, 1, 0));
+    assert_eq!(vec![0, 0, 0, 0], pixel(rotated, 0, 0), "outside the quad");
+
+    let tinted = draw_list::Sprite {
+        tint: (0.0, 1.0, 1.0, 1.0),
+        ..sprite
+    };
+    assert_eq!(vec![0, 0, 0, 255], pixel(tinted, 0, 1));
+}
+
+#[test]
+fn golden_scene() {
+    let texture_atlas = Image::load_png("./res/atlas.png").unwrap();
+    let image = draw(&scene(), &texture_atlas, &[font()], 320, 240);
+    compare_to_golden(&image, "./res/golden/scene.png", 1).unwrap();
+}
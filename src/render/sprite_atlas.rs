@@ -0,0 +1,104 @@
+use super::animation::Clip;
+use super::draw_list::{Icon, TextureRegion};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+// every sprite the game asks for by name, an atlas has to have all of them
+pub const REQUIRED_SPRITES: [&str; 4] = ["playfield", "birdy", "rock", "coin"];
+
+// sprite location within the atlas image, in pixels from the top left corner
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// sprite sheet descriptor, lives next to the atlas image as json
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteAtlas {
+    pub width: u32, // size of the atlas image
+    pub height: u32,
+    pub sprites: HashMap<String, PixelRect>,
+    #[serde(default)]
+    pub animations: HashMap<String, Clip>,
+}
+impl SpriteAtlas {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let sprite_atlas: SpriteAtlas = serde_json::from_str(json)?;
+        for name in REQUIRED_SPRITES {
+            if !sprite_atlas.sprites.contains_key(name) {
+                return Err(format!("sprite atlas is missing \"{}\"", name).into());
+            }
+        }
+        for (name, rect) in sprite_atlas.sprites.iter() {
+            // sums too big for a u32 don't fit either
+            let fits = |start: u32, length: u32, limit: u32| {
+                start.checked_add(length).is_some_and(|end| end <= limit)
+            };
+            if !fits(rect.x, rect.width, sprite_atlas.width)
+                || !fits(rect.y, rect.height, sprite_atlas.height)
+            {
+                return Err(format!("sprite \"{}\" doesn't fit in the atlas", name).into());
+            }
+        }
+        for (name, clip) in sprite_atlas.animations.iter() {
+            if clip.frames.is_empty() {
+                return Err(format!("animation \"{}\" has no frames", name).into());
+            }
+            for frame in clip.frames.iter() {
+                if !sprite_atlas.sprites.contains_key(&frame.sprite) {
+                    return Err(format!(
+                        "animation \"{}\" uses missing sprite \"{}\"",
+                        name, frame.sprite
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(sprite_atlas)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        SpriteAtlas::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    // a sprite to put in a line of text
+    pub fn icon(&self, name: &str) -> Option<Icon> {
+        let rect = self.sprites.get(name)?;
+        Some(Icon {
+            texture_region: self.get(name)?,
+            aspect_ratio: rect.width as f32 / rect.height as f32,
+        })
+    }
+
+    // normalized texture coordinates of a sprite
+    pub fn get(&self, name: &str) -> Option<TextureRegion> {
+        self.sprites.get(name).map(|rect| {
+            (
+                (
+                    rect.x as f32 / self.width as f32,
+                    rect.y as f32 / self.height as f32,
+                ),
+                (
+                    (rect.x + rect.width) as f32 / self.width as f32,
+                    (rect.y + rect.height) as f32 / self.height as f32,
+                ),
+            )
+        })
+    }
+
+    // like get, for sprites that are known to be there (see REQUIRED_SPRITES)
+    pub fn region(&self, name: &str) -> TextureRegion {
+        self.get(name)
+            .unwrap_or_else(|| panic!("no sprite named \"{}\" in the atlas", name))
+    }
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn load_atlas() {
+    let sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    assert_eq!(
+        ((22.0 / 64.0, 22.0 / 64.0), (38.0 / 64.0, 38.0 / 64.0)),
+        sprite_atlas.region("rock")
+    );
+    assert_eq!(
+        ((14.0 / 64.0, 42.0 / 64.0), (22.0 / 64.0, 50.0 / 64.0)),
+        sprite_atlas.region("playfield")
+    );
+    assert_eq!(None, sprite_atlas.get("pipe"));
+}
+
+#[test]
+fn reject_bad_atlases() {
+    // missing most of the required sprites
+    assert!(SpriteAtlas::from_json(
+        r#"{"width": 8, "height": 8, "sprites": {"birdy": {"x": 0, "y": 0, "width": 8, "height": 8}}}"#
+    )
+    .is_err());
+
+    // sprite hanging off the edge
+    let mut sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    sprite_atlas.sprites.get_mut("coin").unwrap().x = 60;
+    let json = serde_json::to_string(&sprite_atlas).unwrap();
+    assert!(SpriteAtlas::from_json(&json).is_err());
+
+    // far enough off the edge to wrap around
+    sprite_atlas.sprites.get_mut("coin").unwrap().x = u32::MAX;
+    let json = serde_json::to_string(&sprite_atlas).unwrap();
+    assert!(SpriteAtlas::from_json(&json).is_err());
+}
+
+#[test]
+fn reject_bad_animations() {
+    let mut sprite_atlas = SpriteAtlas::load("./res/atlas.json").unwrap();
+    sprite_atlas
+        .animations
+        .get_mut("coin_spin")
+        .unwrap()
+        .frames[1]
+        .sprite = "pipe".to_string();
+    let json = serde_json::to_string(&sprite_atlas).unwrap();
+    assert!(SpriteAtlas::from_json(&json).is_err());
+}
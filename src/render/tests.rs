@@ -1,86 +1,52 @@
 use super::*;
 
 #[test]
-fn square_from_edge_positions() {
+fn instance_from_glyph() {
     assert_eq!(
-        [
-            Vert {
-                // rt
-                position: (1.0, 1.0, 0.9),
-                texture_coordinates: (0.125, 0.0),
-            },
-            Vert {
-                // lt
-                position: (-1.0, 1.0, 0.9),
-                texture_coordinates: (0.0, 0.0),
-            },
-            Vert {
-                // lb
-                position: (-1.0, -1.0, 0.9),
-                texture_coordinates: (0.0, 0.125),
-            },
-            Vert {
-                // rt
-                position: (1.0, 1.0, 0.9),
-                texture_coordinates: (0.125, 0.0),
-            },
-            Vert {
-                // lb
-                position: (-1.0, -1.0, 0.9),
-                texture_coordinates: (0.0, 0.125),
-            },
-            Vert {
-                // rb
-                position: (1.0, -1.0, 0.9),
-                texture_coordinates: (0.125, 0.125),
-            },
-        ],
-        super::square_from_edge_positions(
-            -1.0,
-            1.0,
-            1.0,
-            -1.0,
-            0.9,
-            ((0.0, 0.0), (8.0 / 64.0, 8.0 / 64.0)),
-        )
+        Instance {
+            center: (0.0, 0.5),
+            half_size: (1.0, 0.5),
+            rotation: 0.0,
+            depth: draw_list::TEXT_DEPTH,
+            texture_region: (0.25, 0.0, 0.5, 0.125),
+            tint: (0.0, 1.0, 0.0, 1.0),
+        },
+        super::instance_from_glyph(&text::GlyphQuad {
+            left: -1.0,
+            right: 1.0,
+            top: 1.0,
+            bottom: 0.0,
+            texture_region: ((0.25, 0.0), (0.5, 0.125)),
+            color: (0.0, 1.0, 0.0, 1.0),
+            depth: draw_list::TEXT_DEPTH,
+        })
     );
 }
 
 #[test]
-fn square_from_dims() {
+fn instance_from_sprite() {
     assert_eq!(
-        [
-            Vert {
-                // rt
-                position: (1.0, 1.0, 0.6),
-                texture_coordinates: (1.0, 0.0),
-            },
-            Vert {
-                // lt
-                position: (-1.0, 1.0, 0.6),
-                texture_coordinates: (0.0, 0.0),
-            },
-            Vert {
-                // lb
-                position: (-1.0, -1.0, 0.6),
-                texture_coordinates: (0.0, 1.0),
-            },
-            Vert {
-                // rt
-                position: (1.0, 1.0, 0.6),
-                texture_coordinates: (1.0, 0.0),
-            },
-            Vert {
-                // lb
-                position: (-1.0, -1.0, 0.6),
-                texture_coordinates: (0.0, 1.0),
-            },
-            Vert {
-                // rb
-                position: (1.0, -1.0, 0.6),
-                texture_coordinates: (1.0, 1.0),
-            },
-        ],
-        super::square_from_dims(1.0, 1.0, 0.6, (0.0, 0.0), ((0.0, 0.0), (1.0, 1.0)))
+        Instance {
+            center: (0.25, -0.5),
+            half_size: (0.2, 0.05),
+            rotation: 0.3,
+            depth: 0.6,
+            texture_region: (0.0, 0.125, 0.125, 0.0),
+            tint: (1.0, 0.0, 0.0, 0.5),
+        },
+        super::instance_from_sprite(&draw_list::Sprite {
+            center: (0.25, -0.5),
+            half_size: (0.1, 0.1),
+            depth: 0.6,
+            texture_region: ((0.0, 0.0), (0.125, 0.125)),
+            tint: (1.0, 0.0, 0.0, 0.5),
+            bloom: 0.0,
+            transform: draw_list::Transform {
+                rotation: 0.3,
+                flip_x: false,
+                flip_y: true,
+                scale: (2.0, 0.5),
+            },
+        })
     );
 }
@@ -1,185 +1,834 @@
-use glium::Surface;
+use super::draw_list::{
+    Decoration, Icon, Style, TextEffects, TextLayout, TextureRegion, TEXT_DEPTH,
+};
+use super::font::{BitmapChar, Font};
 
-use rusttype::{point, Scale};
+use std::error::Error;
+use std::ops::Range;
+
+use rusttype::gpu_cache::Cache;
+use rusttype::{point, PositionedGlyph, Scale};
 
 #[cfg(test)]
 mod tests;
 
+pub mod distance_field;
+
+const GLYPH_CACHE_SIZE: u32 = 256; // starting width and height of the glyph cache texture, doubles when it runs out of room
+const MAX_GLYPH_CACHE_SIZE: u32 = 4096;
+
+const GLOW_RINGS: usize = 3; // copies of the text at evenly spaced distances, making up a glow
+const GLOW_LAYER_ALPHA: f32 = 0.15; // portion of the glow color's alpha each copy gets, they add up where they overlap
+const LAYER_DEPTH: f32 = 0.0001; // depth between effect layers, so the ones in front pass the depth test
+
+// style for a range of characters, later spans win where they overlap
 #[derive(Debug, Clone, PartialEq)]
-pub struct ColorFmt {
-    glyph_index: usize,
-    color: (f32, f32, f32, f32),
+pub struct StyleSpan {
+    range: Range<usize>, // character indices, not bytes
+    style: Style,
 }
-impl ColorFmt {
-    pub fn new(glyph_index: usize, color: (f32, f32, f32, f32)) -> Self {
-        ColorFmt {
-            glyph_index: glyph_index,
-            color: color,
-        }
+impl StyleSpan {
+    pub fn new(range: Range<usize>, style: Style) -> Self {
+        StyleSpan { range, style }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct SuperString<'a> {
     text: String,
-    font: &'a rusttype::Font<'a>,
-    color_fmts: Vec<ColorFmt>,
+    fonts: &'a [Font], // in order of preference, the first one also sets the line metrics
+    style_spans: Vec<StyleSpan>,
     normalized_height: f32,
+    layout: TextLayout,
+    effects: TextEffects,
 }
 impl<'a> SuperString<'a> {
     pub fn new(
         text: String,
-        font: &'a rusttype::Font<'a>,
-        color_fmts: Vec<ColorFmt>,
+        fonts: &'a [Font],
+        style_spans: Vec<StyleSpan>,
         normalized_height: f32,
     ) -> Self {
         SuperString {
-            text: text,
-            font: font,
-            color_fmts: color_fmts,
-            normalized_height: normalized_height,
+            text,
+            fonts,
+            style_spans,
+            normalized_height,
+            layout: TextLayout::default(),
+            effects: TextEffects::default(),
         }
     }
 
+    pub fn with_layout(mut self, layout: TextLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_effects(mut self, effects: TextEffects) -> Self {
+        self.effects = effects;
+        self
+    }
+
+    // width and height in normalized units, before drawing anything
+    pub fn measure(&self) -> (f32, f32) {
+        // rusttype layout scales linearly, so laying out at the normalized height gives normalized units directly
+        let block = layout_block(self, self.normalized_height);
+        (block.width, block.height)
+    }
+
     pub fn cat(&mut self, other: Self) {
-        let self_glyph_count = {
-            let mut count = 0;
-            for character in self.text.chars() {
-                if self.font.glyph(character).id().0 != 0 {
-                    count += 1;
-                }
+        let offset = self.text.chars().count();
+        self.text += &other.text;
+        for mut style_span in other.style_spans {
+            style_span.range = style_span.range.start + offset..style_span.range.end + offset;
+            self.style_spans.push(style_span);
+        }
+    }
+
+    // characters none of the fonts have, in the order they first show up, which get drawn as the first font's
+    // missing glyph box, icons don't count
+    pub fn missing_characters(&self) -> Vec<char> {
+        let mut missing = Vec::new();
+        for (index, character) in self.text.chars().enumerate() {
+            if character != '\n'
+                && self.style_at(index).icon.is_none()
+                && font_for(self.fonts, character).is_none()
+                && !missing.contains(&character)
+            {
+                missing.push(character);
             }
-            count
+        }
+        missing
+    }
+
+    fn style_at(&self, index: usize) -> Style {
+        self.style_spans
+            .iter()
+            .rev()
+            .find(|style_span| style_span.range.contains(&index))
+            .map_or(Style::default(), |style_span| style_span.style)
+    }
+}
+
+// an underline or strikethrough, edges in pixels from the block's top left corner
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Stroke {
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+    color: (f32, f32, f32, f32),
+}
+
+// a laid out glyph, along with the index of the font it came from and its color
+type BlockGlyph = (PositionedGlyph<'static>, usize, (f32, f32, f32, f32));
+
+// text laid out in lines, glyph positions in pixels from the block's top left corner
+struct Block {
+    glyphs: Vec<BlockGlyph>,
+    bitmap_glyphs: Vec<BitmapGlyph>,
+    icons: Vec<IconGlyph>,
+    strokes: Vec<Stroke>,
+    width: f32,
+    height: f32,
+}
+
+// a glyph from a bitmap font laid out in a block, in pixels from the block's top left corner
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct BitmapGlyph {
+    font: usize,
+    bitmap_char: BitmapChar,
+    left: f32,
+    top: f32,
+    scale: f32, // pixels per font pixel
+    color: (f32, f32, f32, f32),
+}
+
+// an inline icon laid out in a block, in pixels from the block's top left corner
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct IconGlyph {
+    texture_region: TextureRegion,
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    color: (f32, f32, f32, f32),
+}
+
+// a glyph from either kind of font, or an icon, at the size it'll be drawn
+#[derive(Clone)]
+enum ScaledGlyph {
+    Outline(rusttype::ScaledGlyph<'static>),
+    Bitmap {
+        bitmap_char: Option<BitmapChar>, // nothing to draw if the font doesn't have it
+        scale: f32,                      // pixels per font pixel
+        base: f32,                       // font pixels from the top of a line to the baseline
+    },
+    Icon {
+        icon: Icon,
+        height: f32, // from the baseline up
+    },
+}
+impl ScaledGlyph {
+    fn advance(&self) -> f32 {
+        match self {
+            ScaledGlyph::Outline(glyph) => glyph.h_metrics().advance_width,
+            ScaledGlyph::Bitmap {
+                bitmap_char, scale, ..
+            } => bitmap_char.map_or(0.0, |bitmap_char| bitmap_char.advance * scale),
+            ScaledGlyph::Icon { icon, height } => icon.aspect_ratio * height,
+        }
+    }
+}
+
+// one glyph in a line, before alignment
+struct LineGlyph {
+    glyph: ScaledGlyph,
+    font: usize,
+    x: f32,
+    style: Style,
+    whitespace: bool,
+}
+
+// width of a line, not counting trailing whitespace
+fn line_width(line: &[LineGlyph]) -> f32 {
+    line.iter()
+        .rev()
+        .find(|g| !g.whitespace)
+        .map(|g| g.x + g.glyph.advance())
+        .unwrap_or(0.0)
+}
+
+// index of the first font with a glyph for the character, glyph 0 being every font's missing glyph box
+fn font_for(fonts: &[Font], character: char) -> Option<usize> {
+    fonts.iter().position(|font| font.has_glyph(character))
+}
+
+// lay text out with lines of the given pixel height, breaking lines at newlines and wherever they'd get wider
+// than the layout's max width
+fn layout_block(text: &SuperString, height: f32) -> Block {
+    let max_width = text
+        .layout
+        .max_width
+        .map(|max_width| max_width * height / text.normalized_height);
+
+    let mut lines: Vec<Vec<LineGlyph>> = vec![Vec::new()];
+    let mut caret = 0.0;
+    let mut last_glyph = None;
+    let mut line_break = None; // where the current line can be wrapped, just after its last space
+    for (index, character) in text.text.chars().enumerate() {
+        if character == '\n' {
+            lines.push(Vec::new());
+            caret = 0.0;
+            last_glyph = None;
+            line_break = None;
+            continue;
+        }
+
+        // we'll be rendering glyphs at a 1:1 scale
+        let style = text.style_at(index);
+        let glyph_height = height * style.scale;
+        let font = font_for(text.fonts, character).unwrap_or(0);
+        let glyph = match (style.icon, &text.fonts[font]) {
+            // as tall as the text above the baseline, so it sits on the line like a capital letter
+            (Some(icon), _) => ScaledGlyph::Icon {
+                icon,
+                height: text.fonts[0].ascent(glyph_height),
+            },
+            (None, Font::Outline(outline_font)) => ScaledGlyph::Outline(
+                outline_font
+                    .glyph(character)
+                    .scaled(Scale::uniform(glyph_height)),
+            ),
+            (None, Font::Bitmap(bitmap_font)) => ScaledGlyph::Bitmap {
+                bitmap_char: bitmap_font.chars.get(&character).copied(),
+                scale: glyph_height / bitmap_font.line_height,
+                base: bitmap_font.base,
+            },
         };
-        self.text += &other.text;
-        for mut color_fmt in other.color_fmts {
-            color_fmt.glyph_index += self_glyph_count;
-            self.color_fmts.push(color_fmt);
+        // kerning only makes sense between glyphs from the same font
+        if let Some((last_font, last_character)) = last_glyph {
+            if last_font == font && style.icon.is_none() {
+                caret += text.fonts[font].kerning(glyph_height, last_character, character);
+            }
+        }
+        last_glyph = style.icon.is_none().then_some((font, character));
+        let advance = glyph.advance();
+        let whitespace = character.is_whitespace();
+        let line = lines.last_mut().unwrap();
+
+        // too wide, carry the last word over to a new line, or just this glyph if there's no space to break at
+        if max_width.is_some_and(|max_width| caret + advance > max_width)
+            && !whitespace
+            && !line.is_empty()
+        {
+            let mut wrapped = line.split_off(line_break.unwrap_or(line.len()));
+            let start = wrapped.first().map_or(caret, |g| g.x);
+            for g in wrapped.iter_mut() {
+                g.x -= start;
+            }
+            caret -= start;
+            lines.push(wrapped);
+            line_break = None;
+        }
+
+        let line = lines.last_mut().unwrap();
+        line.push(LineGlyph {
+            glyph,
+            font,
+            x: caret,
+            style,
+            whitespace,
+        });
+        caret += advance;
+        if whitespace {
+            line_break = Some(line.len());
+        }
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| line_width(line))
+        .fold(0.0, f32::max);
+
+    let mut glyphs = Vec::new();
+    let mut bitmap_glyphs = Vec::new();
+    let mut icons = Vec::new();
+    let mut strokes: Vec<Stroke> = Vec::new();
+    let mut top = 0.0;
+    let mut bottom = 0.0;
+    for line in lines.iter() {
+        // lines are as tall as their biggest glyph
+        let line_scale = line.iter().map(|g| g.style.scale).fold(0.0, f32::max);
+        let line_height = height * if line.is_empty() { 1.0 } else { line_scale };
+
+        // Determine font ascender (distance from the top of the text to the baseline) and offest baseline down
+        let baseline = top + text.fonts[0].ascent(line_height);
+        let offset = text.layout.align.offset(width - line_width(line));
+        for g in line {
+            let left = offset + g.x;
+            match g.glyph {
+                ScaledGlyph::Outline(ref glyph) => glyphs.push((
+                    glyph.clone().positioned(point(left, baseline)),
+                    g.font,
+                    g.style.color,
+                )),
+                ScaledGlyph::Bitmap {
+                    bitmap_char: Some(bitmap_char),
+                    scale,
+                    base,
+                } => bitmap_glyphs.push(BitmapGlyph {
+                    font: g.font,
+                    bitmap_char,
+                    left: left + bitmap_char.offset.0 * scale,
+                    top: baseline + (bitmap_char.offset.1 - base) * scale,
+                    scale,
+                    color: g.style.color,
+                }),
+                ScaledGlyph::Bitmap {
+                    bitmap_char: None, ..
+                } => (),
+                ScaledGlyph::Icon { icon, height } => icons.push(IconGlyph {
+                    texture_region: icon.texture_region,
+                    left,
+                    top: baseline - height,
+                    width: g.glyph.advance(),
+                    height,
+                    color: g.style.color,
+                }),
+            }
+
+            // decorations, one stroke per glyph, joined up with the previous glyph's if they match
+            let glyph_height = height * g.style.scale;
+            let thickness = f32::max(glyph_height / 16.0, 1.0);
+            let stroke_top = match g.style.decoration {
+                Decoration::None => continue,
+                Decoration::Underline => baseline + thickness,
+                Decoration::Strikethrough => {
+                    baseline - text.fonts[0].ascent(glyph_height) * 0.3 - thickness / 2.0
+                }
+            };
+            let stroke = Stroke {
+                left,
+                right: left + g.glyph.advance(),
+                top: stroke_top,
+                bottom: stroke_top + thickness,
+                color: g.style.color,
+            };
+            match strokes.last_mut() {
+                Some(last)
+                    if (last.top, last.bottom, last.color)
+                        == (stroke.top, stroke.bottom, stroke.color)
+                        && (last.right - stroke.left).abs() < 1.0 =>
+                {
+                    last.right = stroke.right
+                }
+                _ => strokes.push(stroke),
+            }
         }
+        bottom = top + line_height;
+        top += line_height * text.layout.line_spacing;
+    }
+
+    Block {
+        glyphs,
+        bitmap_glyphs,
+        icons,
+        strokes,
+        width,
+        height: bottom,
     }
 }
 
-pub fn render_text_to_texture(
-    f_buff: &mut glium::Frame,
-    disp: &glium::Display,
-    text: &SuperString,
-) -> glium::texture::srgb_texture2d::SrgbTexture2d {
-    // determine text height in screen pixels
-    let height = f_buff.get_dimensions().1 as f32 * text.normalized_height / 2.0;
-    let pixel_height = height.ceil() as usize;
-
-    // we'll be rendering glyphs at a 1:1 scale
-    let scale = Scale {
-        x: height,
-        y: height,
-    };
+// a copy of the text, offset in pixels and drawn in a single color, or the text itself if there's no color
+struct Layer {
+    offset: (f32, f32),
+    color: Option<(f32, f32, f32, f32)>,
+}
+impl Layer {
+    // glyphs keep their own alpha, so fading text fades its effects too
+    fn color(&self, glyph_color: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+        match self.color {
+            Some(color) => (color.0, color.1, color.2, color.3 * glyph_color.3),
+            None => glyph_color,
+        }
+    }
+}
 
-    // Determine font ascender (distance from the top of the text to the baseline) and offest baseline down
-    let v_metrics = text.font.v_metrics(scale);
-    let offset = point(0.0, v_metrics.ascent);
+// offsets all the way around a circle, enough of them that there aren't gaps between neighbours
+fn ring(radius: f32) -> impl Iterator<Item = (f32, f32)> {
+    let directions = ((radius * 4.0).ceil() as usize).clamp(8, 32);
+    (0..directions).map(move |i| {
+        let angle = i as f32 / directions as f32 * std::f32::consts::TAU;
+        (radius * angle.cos(), radius * angle.sin())
+    })
+}
 
-    // Glyphs to draw for the provided text string.
-    let glyphs: Vec<_> = text.font.layout(&text.text, scale, offset).collect();
+// everything to draw for some text with lines of the given pixel height, back to front, ending with the text itself
+fn layers(text: &SuperString, height: f32) -> Vec<Layer> {
+    let mut layers = Vec::new();
+    if let Some(glow) = text.effects.glow {
+        for ring_index in (1..=GLOW_RINGS).rev() {
+            let color = (
+                glow.color.0,
+                glow.color.1,
+                glow.color.2,
+                glow.color.3 * GLOW_LAYER_ALPHA,
+            );
+            let radius = glow.radius * height * ring_index as f32 / GLOW_RINGS as f32;
+            layers.extend(ring(radius).map(|offset| Layer {
+                offset,
+                color: Some(color),
+            }));
+        }
+    }
+    if let Some(shadow) = text.effects.shadow {
+        layers.push(Layer {
+            offset: (shadow.offset.0 * height, shadow.offset.1 * height),
+            color: Some(shadow.color),
+        });
+    }
+    if let Some(outline) = text.effects.outline {
+        layers.extend(ring(outline.width * height).map(|offset| Layer {
+            offset,
+            color: Some(outline.color),
+        }));
+    }
+    layers.push(Layer {
+        offset: (0.0, 0.0),
+        color: None,
+    });
+    layers
+}
 
-    // Determine textbox width by summing character widths and advance lengths
-    let width = glyphs
+// layers from the back to the front, each with a depth that puts it in front of the ones behind it
+fn back_to_front(layers: &[Layer]) -> impl Iterator<Item = (f32, &Layer)> {
+    layers
         .iter()
         .rev()
-        .map(|g| g.position().x as f32 + g.unpositioned().h_metrics().advance_width)
-        .next()
-        .unwrap_or(0.0)
-        .ceil() as usize;
+        .enumerate()
+        .rev()
+        .map(|(behind, layer)| (TEXT_DEPTH + behind as f32 * LAYER_DEPTH, layer))
+}
+
+// pixels that effects can reach past the edges of the text, for text with lines of the given pixel height
+pub fn margin(text: &SuperString, height: f32) -> usize {
+    layers(text, height)
+        .iter()
+        .map(|layer| f32::max(layer.offset.0.abs(), layer.offset.1.abs()))
+        .fold(0.0, f32::max)
+        .ceil() as usize
+}
+
+// rasterize text with lines of the given pixel height, effects included, returns rgba pixel data along with its
+// width and height, the text itself starts a margin() in from the top left corner
+pub fn rasterize(text: &SuperString, height: f32) -> (Vec<f32>, usize, usize) {
+    let block = layout_block(text, height);
+    let margin = margin(text, height);
+    let width = block.width.ceil() as usize + margin * 2;
+    let pixel_height = block.height.ceil() as usize + margin * 2;
 
     let mut pixel_data = vec![0.0; width * pixel_height * 4]; // multiply by 4 because we're using rgba, thus 4 channels
+    let mut write_pixel = |x: i32, y: i32, color: (f32, f32, f32, f32), v: f32| {
+        // clip our writes to the texture to stay within its bounds
+        if x >= 0 && x < width as i32 && y >= 0 && y < pixel_height as i32 {
+            let x = x as usize;
+            let y = y as usize;
+            let index = (x + y * width) * 4;
 
-    let mut current_color = (1.0, 1.0, 1.0, 1.0);
-    let mut colors = text.color_fmts.iter();
-    let mut next_color = colors.next();
+            // blend over whatever's already there, which could be another layer or a neighbouring glyph
+            let alpha = color.3 * v;
+            let below = pixel_data[index + 3] * (1.0 - alpha);
+            let blended = alpha + below;
+            if blended > 0.0 {
+                for (channel, source) in [color.0, color.1, color.2].into_iter().enumerate() {
+                    pixel_data[index + channel] =
+                        (source * alpha + pixel_data[index + channel] * below) / blended;
+                }
+            }
+            pixel_data[index + 3] = blended;
+        }
+    };
 
-    for (index, g) in glyphs.into_iter().enumerate() {
-	if let Some(color_fmt) = next_color {
-            if color_fmt.glyph_index == index {
-                current_color = color_fmt.color;
-                next_color = colors.next();
+    for layer in layers(text, height) {
+        let offset = rusttype::vector(
+            layer.offset.0 + margin as f32,
+            layer.offset.1 + margin as f32,
+        );
+        for (g, _, color) in block.glyphs.iter() {
+            let g = g.unpositioned().clone().positioned(g.position() + offset);
+            let color = layer.color(*color);
+            if let Some(bb) = g.pixel_bounding_box() {
+                g.draw(|x, y, v| {
+                    // ensure pixel value is within the range 0.0..=1.0
+                    write_pixel(
+                        x as i32 + bb.min.x,
+                        y as i32 + bb.min.y,
+                        color,
+                        v.clamp(0.0, 1.0),
+                    );
+                })
             }
         }
-        if let Some(bb) = g.pixel_bounding_box() {
-            g.draw(|x, y, v| {
-                // ensure pixel value is within the range 0.0..=1.0
-                let v = v.clamp(0.0, 1.0);
-
-                let x = x as i32 + bb.min.x;
-                let y = y as i32 + bb.min.y;
-
-                // clip our writes to the texture to stay within its bounds
-                if x >= 0 && x < width as i32 && y >= 0 && y < pixel_height as i32 {
-                    let x = x as usize;
-                    let y = y as usize;
-                    let index = (x + y * width) * 4;
-                    // write each color channel
-                    pixel_data[index + 0] = current_color.0; // R
-                    pixel_data[index + 1] = current_color.1; // G
-                    pixel_data[index + 2] = current_color.2; // B
-                    pixel_data[index + 3] = current_color.3 * v; // A
+        for glyph in block.bitmap_glyphs.iter() {
+            let Font::Bitmap(font) = &text.fonts[glyph.font] else {
+                continue;
+            };
+            let page = &font.pages[glyph.bitmap_char.page];
+            let rect = glyph.bitmap_char.rect;
+            let color = layer.color(glyph.color);
+            let (left, top) = (glyph.left + offset.x, glyph.top + offset.y);
+            let right = left + rect.width as f32 * glyph.scale;
+            let bottom = top + rect.height as f32 * glyph.scale;
+            for y in top.round() as i32..bottom.round() as i32 {
+                for x in left.round() as i32..right.round() as i32 {
+                    // nearest neighbour, like the gpu samples the page
+                    let page_x = ((x as f32 + 0.5 - left) / glyph.scale) as u32;
+                    let page_y = ((y as f32 + 0.5 - top) / glyph.scale) as u32;
+                    let page_x = rect.x + page_x.min(rect.width - 1);
+                    let page_y = rect.y + page_y.min(rect.height - 1);
+                    let index = (page_x + page_y * page.width) as usize * 4;
+                    let texel = |channel: usize| page.data[index + channel] as f32 / 255.0;
+                    write_pixel(
+                        x,
+                        y,
+                        (
+                            color.0 * texel(0),
+                            color.1 * texel(1),
+                            color.2 * texel(2),
+                            color.3,
+                        ),
+                        texel(3),
+                    );
                 }
-            })
+            }
+        }
+        for stroke in block.strokes.iter() {
+            let (left, right) = (stroke.left + offset.x, stroke.right + offset.x);
+            let (top, bottom) = (stroke.top + offset.y, stroke.bottom + offset.y);
+            let bottom = f32::max(bottom.round(), top.round() + 1.0) as i32;
+            for y in top.round() as i32..bottom {
+                for x in left.round() as i32..right.round() as i32 {
+                    write_pixel(x, y, layer.color(stroke.color), 1.0);
+                }
+            }
         }
     }
 
-    // build texture from raw color data
-    let raw_texture = glium::texture::RawImage2d::from_raw_rgba(
-        pixel_data,
-        (width.try_into().unwrap(), pixel_height.try_into().unwrap()),
-    );
-    glium::texture::srgb_texture2d::SrgbTexture2d::new(disp, raw_texture).unwrap()
-}
-
-pub fn render_text(
-    f_buff: &mut glium::Frame,
-    disp: &glium::Display,
-    shdr: &glium::program::Program,
-    window_aspect_ratio: f32,
-    text: SuperString,
-    position: (f32, f32),
-) {
-    let text_texture = render_text_to_texture(f_buff, disp, &text);
-
-    // simple quad model, should be located on the upper left corner of the screen
-    let text_model = super::square_from_edge_positions(
-        position.0,
-        position.0
-            + (text.normalized_height * (text_texture.width() as f32 / text_texture.height() as f32)),
-        position.1,
-        position.1 - text.normalized_height,
-        0.0,
-        ((0.0, 0.0), (1.0, 1.0)),
-    );
-
-    // draw the text text... ure
-    (*f_buff)
-        .draw(
-            // draw vertices to framebuffer
-            &glium::VertexBuffer::new(disp, &text_model).unwrap(),
-            &glium::index::NoIndices(
-                // not using indexed rendering
-                glium::index::PrimitiveType::TrianglesList, // polygon type
-            ),
-            shdr, // shader program
-            &glium::uniform! {
-                window_aspect_ratio: window_aspect_ratio,
-                texture_atlas: text_texture.sampled()
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            },
-            &glium::DrawParameters {
-                // draw parameters
-                depth: glium::Depth {
-                    test: glium::draw_parameters::DepthTest::IfLess,
-                    write: true,
-                    ..Default::default()
+    (pixel_data, width, pixel_height)
+}
+
+// height of a line of text in screen pixels, for a framebuffer of the given height
+pub fn pixel_height(text: &SuperString, framebuffer_height: u32) -> f32 {
+    framebuffer_height as f32 * text.normalized_height / 2.0
+}
+
+// one glyph ready to be drawn, edges in playfield coordinates
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GlyphQuad {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+    pub texture_region: TextureRegion,
+    pub color: (f32, f32, f32, f32),
+    pub depth: f32,
+}
+
+// everything needed to draw some text on the gpu
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextQuads {
+    pub glyphs: Vec<GlyphQuad>,         // sampling the glyph cache texture
+    pub bitmap_glyphs: Vec<BitmapQuad>, // sampling bitmap font pages
+    pub icons: Vec<GlyphQuad>,          // sampling the sprite atlas
+    pub strokes: Vec<GlyphQuad>, // underlines and strikethroughs, sampling a solid white texture
+}
+
+// a bitmap font glyph ready to be drawn, along with which font and page it samples
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BitmapQuad {
+    pub font: usize,
+    pub page: usize,
+    pub quad: GlyphQuad,
+}
+
+// quads for a laid out string's bitmap font glyphs, in every layer
+fn bitmap_quads<'a>(
+    fonts: &'a [Font],
+    block: &'a Block,
+    layers: &'a [Layer],
+    (left, top): (f32, f32),
+    unit: f32,
+) -> impl Iterator<Item = BitmapQuad> + 'a {
+    back_to_front(layers).flat_map(move |(depth, layer)| {
+        let left = left + layer.offset.0 * unit;
+        let top = top - layer.offset.1 * unit;
+        block.bitmap_glyphs.iter().filter_map(move |glyph| {
+            let Font::Bitmap(font) = &fonts[glyph.font] else {
+                return None;
+            };
+            let page = &font.pages[glyph.bitmap_char.page];
+            let rect = glyph.bitmap_char.rect;
+            let (width, height) = (page.width as f32, page.height as f32);
+            Some(BitmapQuad {
+                font: glyph.font,
+                page: glyph.bitmap_char.page,
+                quad: GlyphQuad {
+                    left: left + glyph.left * unit,
+                    right: left + (glyph.left + rect.width as f32 * glyph.scale) * unit,
+                    top: top - glyph.top * unit,
+                    bottom: top - (glyph.top + rect.height as f32 * glyph.scale) * unit,
+                    texture_region: (
+                        (rect.x as f32 / width, rect.y as f32 / height),
+                        (
+                            (rect.x + rect.width) as f32 / width,
+                            (rect.y + rect.height) as f32 / height,
+                        ),
+                    ),
+                    color: layer.color(glyph.color),
+                    depth,
                 },
-                blend: glium::draw_parameters::Blend::alpha_blending(),
-                ..Default::default()
-            },
+            })
+        })
+    })
+}
+
+// solid quads for a laid out string's underlines and strikethroughs, in every layer
+fn stroke_quads<'a>(
+    block: &'a Block,
+    layers: &'a [Layer],
+    (left, top): (f32, f32),
+    unit: f32,
+) -> impl Iterator<Item = GlyphQuad> + 'a {
+    back_to_front(layers).flat_map(move |(depth, layer)| {
+        let left = left + layer.offset.0 * unit;
+        let top = top - layer.offset.1 * unit;
+        block.strokes.iter().map(move |stroke| GlyphQuad {
+            left: left + stroke.left * unit,
+            right: left + stroke.right * unit,
+            top: top - stroke.top * unit,
+            bottom: top - stroke.bottom * unit,
+            texture_region: ((0.0, 0.0), (1.0, 1.0)),
+            color: layer.color(stroke.color),
+            depth,
+        })
+    })
+}
+
+// quads for a laid out string's icons, in every layer
+fn icon_quads<'a>(
+    block: &'a Block,
+    layers: &'a [Layer],
+    (left, top): (f32, f32),
+    unit: f32,
+) -> impl Iterator<Item = GlyphQuad> + 'a {
+    back_to_front(layers).flat_map(move |(depth, layer)| {
+        let left = left + layer.offset.0 * unit;
+        let top = top - layer.offset.1 * unit;
+        block.icons.iter().map(move |icon| GlyphQuad {
+            left: left + icon.left * unit,
+            right: left + (icon.left + icon.width) * unit,
+            top: top - icon.top * unit,
+            bottom: top - (icon.top + icon.height) * unit,
+            texture_region: icon.texture_region,
+            color: layer.color(icon.color),
+            depth,
+        })
+    })
+}
+
+// icons aren't part of rasterize's pixels, these go on top of them, for text with lines of the given pixel height
+// whose top left corner is at the given position
+pub fn positioned_icons(
+    text: &SuperString,
+    height: f32,
+    top_left: (f32, f32),
+    unit: f32,
+) -> Vec<GlyphQuad> {
+    icon_quads(
+        &layout_block(text, height),
+        &layers(text, height),
+        top_left,
+        unit,
+    )
+    .collect()
+}
+
+// glyphs rasterized once and packed into a shared texture, so text doesn't need a texture of its own every frame,
+// keyed by index into the font list, so every string drawn with it should use the same fonts
+pub struct GlyphCache {
+    cache: Cache<'static>,
+    texture: glium::texture::srgb_texture2d::SrgbTexture2d,
+}
+impl GlyphCache {
+    pub fn new(disp: &glium::Display) -> Result<Self, Box<dyn Error>> {
+        Ok(GlyphCache {
+            cache: Cache::builder()
+                .dimensions(GLYPH_CACHE_SIZE, GLYPH_CACHE_SIZE)
+                .build(),
+            texture: Self::empty_texture(disp, GLYPH_CACHE_SIZE)?,
+        })
+    }
+
+    fn empty_texture(
+        disp: &glium::Display,
+        size: u32,
+    ) -> Result<glium::texture::srgb_texture2d::SrgbTexture2d, Box<dyn Error>> {
+        Ok(
+            glium::texture::srgb_texture2d::SrgbTexture2d::empty_with_mipmaps(
+                disp,
+                glium::texture::MipmapsOption::NoMipmap,
+                size,
+                size,
+            )?,
         )
-        .unwrap();
+    }
+
+    pub fn texture(&self) -> &glium::texture::srgb_texture2d::SrgbTexture2d {
+        &self.texture
+    }
+
+    // lay out every string at its position, caching any glyphs that aren't in the texture yet
+    pub fn quads(
+        &mut self,
+        disp: &glium::Display,
+        texts: &[(SuperString, (f32, f32))],
+        framebuffer_height: u32,
+    ) -> TextQuads {
+        let laid_out: Vec<_> = texts
+            .iter()
+            .map(|(text, position)| {
+                let height = pixel_height(text, framebuffer_height);
+                // playfield units per pixel
+                let unit = if height > 0.0 {
+                    text.normalized_height / height
+                } else {
+                    0.0
+                };
+                let block = layout_block(text, height);
+                let left = position.0 - text.layout.align.offset(block.width * unit);
+                (text, block, layers(text, height), (left, position.1), unit)
+            })
+            .collect();
+        for (_, block, ..) in laid_out.iter() {
+            for (glyph, font, _) in block.glyphs.iter() {
+                self.cache.queue_glyph(*font, glyph.clone());
+            }
+        }
+
+        // upload whatever's new, starting over with a bigger texture if they don't all fit
+        loop {
+            let texture = &self.texture;
+            let cached = self.cache.cache_queued(|rect, coverage| {
+                // white, so the glyph's color can be applied as a tint
+                let rgba: Vec<u8> = coverage.iter().flat_map(|&a| [255, 255, 255, a]).collect();
+                texture.write(
+                    glium::Rect {
+                        left: rect.min.x,
+                        bottom: rect.min.y,
+                        width: rect.width(),
+                        height: rect.height(),
+                    },
+                    glium::texture::RawImage2d {
+                        data: std::borrow::Cow::Owned(rgba),
+                        width: rect.width(),
+                        height: rect.height(),
+                        format: glium::texture::ClientFormat::U8U8U8U8,
+                    },
+                );
+            });
+            let size = self.cache.dimensions().0;
+            match cached {
+                Ok(_) => break,
+                Err(_) if size < MAX_GLYPH_CACHE_SIZE => {
+                    self.cache
+                        .to_builder()
+                        .dimensions(size * 2, size * 2)
+                        .rebuild(&mut self.cache);
+                    self.texture = Self::empty_texture(disp, size * 2).unwrap();
+                }
+                Err(_) => {
+                    // way too much text, draw what's already cached
+                    self.cache.clear_queue();
+                    break;
+                }
+            }
+        }
+
+        // every layer is a copy of the same glyphs, so each one shifts the cached glyph quads
+        let mut quads = TextQuads::default();
+        for (text, block, layers, (left, top), unit) in laid_out {
+            for (depth, layer) in back_to_front(&layers) {
+                let left = left + layer.offset.0 * unit;
+                let top = top - layer.offset.1 * unit;
+                for (glyph, font, color) in block.glyphs.iter() {
+                    if let Ok(Some((uv, pixels))) = self.cache.rect_for(*font, glyph) {
+                        quads.glyphs.push(GlyphQuad {
+                            left: left + pixels.min.x as f32 * unit,
+                            right: left + pixels.max.x as f32 * unit,
+                            top: top - pixels.min.y as f32 * unit,
+                            bottom: top - pixels.max.y as f32 * unit,
+                            texture_region: ((uv.min.x, uv.min.y), (uv.max.x, uv.max.y)),
+                            color: layer.color(*color),
+                            depth,
+                        });
+                    }
+                }
+            }
+            quads.bitmap_glyphs.extend(bitmap_quads(
+                text.fonts,
+                &block,
+                &layers,
+                (left, top),
+                unit,
+            ));
+            quads
+                .icons
+                .extend(icon_quads(&block, &layers, (left, top), unit));
+            quads
+                .strokes
+                .extend(stroke_quads(&block, &layers, (left, top), unit));
+        }
+        quads
+    }
 }
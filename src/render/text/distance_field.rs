@@ -0,0 +1,343 @@
+use super::super::draw_list::{TextEffects, TEXT_DEPTH};
+use super::super::font::Font;
+use super::{
+    bitmap_quads, icon_quads, layers, layout_block, pixel_height, stroke_quads, BitmapQuad,
+    GlyphQuad, SuperString, LAYER_DEPTH,
+};
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use rusttype::{point, GlyphId, Scale};
+
+#[cfg(test)]
+mod tests;
+
+const FIELD_SIZE: f32 = 32.0; // pixel height glyphs are rasterized at before turning them into distance fields
+const SPREAD: usize = 6; // texels of distance a field covers on either side of a glyph's edges
+const ATLAS_SIZE: u32 = 256; // starting width and height of the atlas texture, doubles when it runs out of room
+const MAX_ATLAS_SIZE: u32 = 4096;
+
+// a glyph's signed distance field, 0.5 on its edges, rising inside and falling outside
+struct Field {
+    offset: (i32, i32), // field texels from the glyph's origin to the field's top left corner
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+// turn coverage into distances, by finding the nearest texel on the other side of the edge from each one
+fn distance_field(coverage: &[f32], width: usize, height: usize) -> Vec<u8> {
+    let inside = |x: usize, y: usize| coverage[x + y * width] >= 0.5;
+    let mut data = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let here = inside(x, y);
+            let mut nearest = SPREAD as f32 + 0.5; // as far as it goes, if there's nothing closer
+            for other_y in y.saturating_sub(SPREAD)..(y + SPREAD + 1).min(height) {
+                for other_x in x.saturating_sub(SPREAD)..(x + SPREAD + 1).min(width) {
+                    if inside(other_x, other_y) != here {
+                        let (dx, dy) = (other_x as f32 - x as f32, other_y as f32 - y as f32);
+                        nearest = f32::min(nearest, (dx * dx + dy * dy).sqrt());
+                    }
+                }
+            }
+            // the edge is halfway between the two texels
+            let distance = nearest - 0.5;
+            let signed = if here { distance } else { -distance };
+            let value = (signed / SPREAD as f32 * 0.5 + 0.5).clamp(0.0, 1.0);
+            data.push((value * 255.0).round() as u8);
+        }
+    }
+    data
+}
+
+fn field(font: &rusttype::Font<'static>, id: GlyphId) -> Field {
+    let glyph = font
+        .glyph(id)
+        .scaled(Scale::uniform(FIELD_SIZE))
+        .positioned(point(0.0, 0.0));
+    let bounds = match glyph.pixel_bounding_box() {
+        Some(bounds) => bounds,
+        None => rusttype::Rect {
+            min: point(0, 0),
+            max: point(0, 0),
+        },
+    };
+
+    // padded so the distances outside the glyph fit
+    let width = bounds.width() as usize + SPREAD * 2;
+    let height = bounds.height() as usize + SPREAD * 2;
+    let mut coverage = vec![0.0; width * height];
+    glyph.draw(|x, y, v| coverage[x as usize + SPREAD + (y as usize + SPREAD) * width] = v);
+
+    Field {
+        offset: (bounds.min.x - SPREAD as i32, bounds.min.y - SPREAD as i32),
+        width: width as u32,
+        height: height as u32,
+        data: distance_field(&coverage, width, height),
+    }
+}
+
+// outline and glow for one glyph, done by the shader, widths in distance field units where the edge is at 0.5
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FieldEffects {
+    pub outline_width: f32,
+    pub outline_color: (f32, f32, f32, f32),
+    pub glow_radius: f32,
+    pub glow_color: (f32, f32, f32, f32),
+}
+impl FieldEffects {
+    // for a glyph of the given style scale and color, faded along with it
+    fn new(effects: &TextEffects, scale: f32, color: (f32, f32, f32, f32)) -> Self {
+        // effects are in line heights, a line height is FIELD_SIZE texels at a scale of 1, SPREAD texels is 0.5
+        let units = |line_heights: f32| line_heights * FIELD_SIZE / scale / SPREAD as f32 * 0.5;
+        let fade = |c: (f32, f32, f32, f32)| (c.0, c.1, c.2, c.3 * color.3);
+        let (outline_width, outline_color) = match effects.outline {
+            Some(outline) => (units(outline.width), fade(outline.color)),
+            None => (0.0, (0.0, 0.0, 0.0, 0.0)),
+        };
+        let outline_width = outline_width.min(0.5);
+        let (glow_radius, glow_color) = match effects.glow {
+            Some(glow) => (units(glow.radius), fade(glow.color)),
+            None => (0.0, (0.0, 0.0, 0.0, 0.0)),
+        };
+        FieldEffects {
+            outline_width,
+            outline_color,
+            // whatever's left of the field's spread past the outline
+            glow_radius: glow_radius.min(0.5 - outline_width),
+            glow_color,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FieldQuad {
+    pub quad: GlyphQuad, // sampling the distance field atlas
+    pub effects: FieldEffects,
+}
+
+// everything needed to draw some distance field text on the gpu
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FieldQuads {
+    pub glyphs: Vec<FieldQuad>,
+    pub bitmap_glyphs: Vec<BitmapQuad>, // bitmap fonts are drawn as they are, they're pixel art anyway
+    pub icons: Vec<GlyphQuad>,          // sampling the sprite atlas, like the sprites they are
+    pub strokes: Vec<GlyphQuad>, // underlines and strikethroughs, sampling a solid white texture
+}
+
+// distance fields of glyphs, generated once at a fixed size and packed into a shared texture, so they can be drawn
+// sharp at any size, keyed by index into the font list like GlyphCache
+pub struct DistanceFieldCache {
+    fields: HashMap<(usize, GlyphId), Field>,
+    placed: HashMap<(usize, GlyphId), (u32, u32)>, // top left corner in the atlas
+    shelf: (u32, u32, u32),                        // left, top and height of the row being filled
+    size: u32,
+    texture: glium::texture::Texture2d,
+}
+impl DistanceFieldCache {
+    pub fn new(disp: &glium::Display) -> Result<Self, Box<dyn Error>> {
+        Ok(DistanceFieldCache {
+            fields: HashMap::new(),
+            placed: HashMap::new(),
+            shelf: (0, 0, 0),
+            size: ATLAS_SIZE,
+            texture: Self::empty_texture(disp, ATLAS_SIZE)?,
+        })
+    }
+
+    fn empty_texture(
+        disp: &glium::Display,
+        size: u32,
+    ) -> Result<glium::texture::Texture2d, Box<dyn Error>> {
+        // linear rather than srgb, these are distances rather than colors
+        Ok(glium::texture::Texture2d::empty_with_format(
+            disp,
+            glium::texture::UncompressedFloatFormat::U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            size,
+            size,
+        )?)
+    }
+
+    pub fn texture(&self) -> &glium::texture::Texture2d {
+        &self.texture
+    }
+
+    // find room for a field on the current row, or the next one, returns None if the atlas is full
+    fn place(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (mut left, mut top, mut row_height) = self.shelf;
+        if left + width > self.size {
+            (left, top, row_height) = (0, top + row_height, 0);
+        }
+        if left + width > self.size || top + height > self.size {
+            return None;
+        }
+        self.shelf = (left + width, top, row_height.max(height));
+        Some((left, top))
+    }
+
+    fn upload(&self, field: &Field, (left, top): (u32, u32)) {
+        self.texture.write(
+            glium::Rect {
+                left,
+                bottom: top,
+                width: field.width,
+                height: field.height,
+            },
+            glium::texture::RawImage2d {
+                data: std::borrow::Cow::Borrowed(&field.data[..]),
+                width: field.width,
+                height: field.height,
+                format: glium::texture::ClientFormat::U8,
+            },
+        );
+    }
+
+    // generate and upload a glyph's field if it isn't in the atlas yet, starting over with a bigger atlas if it's full
+    fn cache(&mut self, disp: &glium::Display, fonts: &[Font], key: (usize, GlyphId)) {
+        if self.placed.contains_key(&key) {
+            return;
+        }
+        let Font::Outline(font) = &fonts[key.0] else {
+            return;
+        };
+        let field = field(font, key.1);
+        loop {
+            if let Some(corner) = self.place(field.width, field.height) {
+                self.upload(&field, corner);
+                self.placed.insert(key, corner);
+                self.fields.insert(key, field);
+                return;
+            }
+            if self.size >= MAX_ATLAS_SIZE {
+                // way too many glyphs, they just won't show up
+                return;
+            }
+            self.size *= 2;
+            self.texture = Self::empty_texture(disp, self.size).unwrap();
+            self.shelf = (0, 0, 0);
+            self.placed.clear();
+            let mut keys: Vec<_> = self.fields.keys().copied().collect();
+            keys.sort();
+            for key in keys {
+                let (width, height) = (self.fields[&key].width, self.fields[&key].height);
+                if let Some(corner) = self.place(width, height) {
+                    self.upload(&self.fields[&key], corner);
+                    self.placed.insert(key, corner);
+                }
+            }
+        }
+    }
+
+    // lay out every string at its position, generating fields for any glyphs that aren't in the atlas yet
+    pub fn quads(
+        &mut self,
+        disp: &glium::Display,
+        texts: &[(SuperString, (f32, f32))],
+        framebuffer_height: u32,
+    ) -> FieldQuads {
+        // laid out exactly like GlyphCache does, so switching between them doesn't move anything
+        let laid_out: Vec<_> = texts
+            .iter()
+            .filter_map(|(text, position)| {
+                let height = pixel_height(text, framebuffer_height);
+                if height <= 0.0 {
+                    return None;
+                }
+                let unit = text.normalized_height / height;
+                let block = layout_block(text, height);
+                let left = position.0 - text.layout.align.offset(block.width * unit);
+                Some((text, block, height, (left, position.1), unit))
+            })
+            .collect();
+        // all of them before any quads, in case the atlas grows and everything moves
+        for (text, block, ..) in laid_out.iter() {
+            for (glyph, font, _) in block.glyphs.iter() {
+                self.cache(disp, text.fonts, (*font, glyph.id()));
+            }
+        }
+
+        let mut quads = FieldQuads::default();
+        for (text, block, height, (left, top), unit) in laid_out {
+            // the shadow is another copy of the glyphs behind them, without a fill of its own
+            let shadow = text.effects.shadow.map(|shadow| {
+                let offset = (shadow.offset.0 * height, shadow.offset.1 * height);
+                (offset, shadow.color)
+            });
+            let copies = shadow.into_iter().map(Some).chain([None]);
+            for copy in copies {
+                let (offset, depth) = match copy {
+                    Some((offset, _)) => (offset, TEXT_DEPTH + LAYER_DEPTH),
+                    None => ((0.0, 0.0), TEXT_DEPTH),
+                };
+                let left = left + offset.0 * unit;
+                let top = top - offset.1 * unit;
+                for (glyph, font, color) in block.glyphs.iter() {
+                    let key = (*font, glyph.id());
+                    let (Some(field), Some(&(x, y))) =
+                        (self.fields.get(&key), self.placed.get(&key))
+                    else {
+                        continue;
+                    };
+
+                    // pixels per field texel
+                    let scale = glyph.scale().y / FIELD_SIZE;
+                    let position = glyph.position();
+                    let field_left = position.x + field.offset.0 as f32 * scale;
+                    let field_top = position.y + field.offset.1 as f32 * scale;
+                    let size = self.size as f32;
+                    let mut effects =
+                        FieldEffects::new(&text.effects, glyph.scale().y / height, *color);
+                    let color = match copy {
+                        Some((_, shadow_color)) => {
+                            effects.outline_color = (
+                                shadow_color.0,
+                                shadow_color.1,
+                                shadow_color.2,
+                                shadow_color.3 * color.3,
+                            );
+                            effects.glow_color.3 = 0.0;
+                            (0.0, 0.0, 0.0, 0.0)
+                        }
+                        None => *color,
+                    };
+                    quads.glyphs.push(FieldQuad {
+                        quad: GlyphQuad {
+                            left: left + field_left * unit,
+                            right: left + (field_left + field.width as f32 * scale) * unit,
+                            top: top - field_top * unit,
+                            bottom: top - (field_top + field.height as f32 * scale) * unit,
+                            texture_region: (
+                                (x as f32 / size, y as f32 / size),
+                                (
+                                    (x + field.width) as f32 / size,
+                                    (y + field.height) as f32 / size,
+                                ),
+                            ),
+                            color,
+                            depth,
+                        },
+                        effects,
+                    });
+                }
+            }
+            let layers = layers(text, height);
+            quads.bitmap_glyphs.extend(bitmap_quads(
+                text.fonts,
+                &block,
+                &layers,
+                (left, top),
+                unit,
+            ));
+            quads
+                .icons
+                .extend(icon_quads(&block, &layers, (left, top), unit));
+            quads
+                .strokes
+                .extend(stroke_quads(&block, &layers, (left, top), unit));
+        }
+        quads
+    }
+}
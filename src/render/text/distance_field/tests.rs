@@ -0,0 +1,88 @@
+use super::*;
+
+use super::super::super::draw_list::{Glow, Outline, WHITE};
+
+#[test]
+fn square_distances() {
+    // a 4x4 square in the middle of a 20x20 field
+    let size = 20;
+    let coverage: Vec<f32> = (0..size * size)
+        .map(|i| {
+            let (x, y) = (i % size, i / size);
+            if (8..12).contains(&x) && (8..12).contains(&y) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let field = distance_field(&coverage, size, size);
+    let at = |x: usize, y: usize| field[x + y * size];
+
+    // half a texel either side of the edge
+    assert!(at(8, 10) > 128 && at(8, 10) < 140);
+    assert!(at(7, 10) < 128 && at(7, 10) > 116);
+    assert!(at(9, 10) > at(8, 10), "rising inside");
+    assert!(at(5, 10) < at(7, 10), "falling outside");
+    assert_eq!(0, at(0, 0), "past the spread");
+}
+
+#[test]
+fn glyph_fields() {
+    let font_data: Vec<u8> = std::fs::read("./res/Octoville.otf").unwrap();
+    let font = rusttype::Font::try_from_vec(font_data).unwrap();
+    let glyph = font.glyph('A');
+    let bounds = glyph
+        .clone()
+        .scaled(Scale::uniform(FIELD_SIZE))
+        .positioned(point(0.0, 0.0))
+        .pixel_bounding_box()
+        .unwrap();
+
+    let field = field(&font, glyph.id());
+    assert_eq!(bounds.width() as u32 + SPREAD as u32 * 2, field.width);
+    assert_eq!(bounds.height() as u32 + SPREAD as u32 * 2, field.height);
+    assert_eq!(bounds.min.x - SPREAD as i32, field.offset.0);
+    assert_eq!((field.width * field.height) as usize, field.data.len());
+    assert!(field.data.iter().any(|&d| d > 128), "some of it's inside");
+    assert!(
+        field.data[..field.width as usize].iter().all(|&d| d == 0),
+        "the padding is all outside"
+    );
+
+    // glyphs without an outline, like spaces, still get an empty field
+    let space = super::field(&font, font.glyph(' ').id());
+    assert!(space.data.iter().all(|&d| d == 0));
+}
+
+#[test]
+fn effects_in_field_units() {
+    let effects = TextEffects {
+        outline: Some(Outline {
+            width: 0.05,
+            color: (0.0, 0.0, 0.0, 1.0),
+        }),
+        shadow: None,
+        glow: Some(Glow {
+            radius: 1.0,
+            color: (1.0, 1.0, 0.0, 1.0),
+        }),
+    };
+    let field_effects = FieldEffects::new(&effects, 1.0, (1.0, 1.0, 1.0, 0.5));
+    let outline_texels = 0.05 * FIELD_SIZE;
+    assert!((field_effects.outline_width - outline_texels / SPREAD as f32 * 0.5).abs() < 1e-6);
+    assert_eq!((0.0, 0.0, 0.0, 0.5), field_effects.outline_color, "faded");
+    assert!(
+        (field_effects.outline_width + field_effects.glow_radius - 0.5).abs() < 1e-6,
+        "the glow only goes as far as the field does"
+    );
+
+    // bigger glyphs have more texels per line height, so the same effects are thinner in field units
+    let bigger = FieldEffects::new(&effects, 2.0, WHITE);
+    assert!((bigger.outline_width - field_effects.outline_width / 2.0).abs() < 1e-6);
+
+    assert_eq!(
+        0.0,
+        FieldEffects::new(&TextEffects::default(), 1.0, WHITE).outline_width
+    );
+}
@@ -1,69 +1,486 @@
 use super::*;
 
+use super::super::draw_list::{Align, ICON_CHARACTER};
+use super::super::font::BitmapFont;
+use super::super::markup;
+use super::super::software::Image;
+
+fn red() -> Style {
+    Style {
+        color: (1.0, 0.0, 0.0, 1.0),
+        ..Style::default()
+    }
+}
+
 #[test]
 fn super_string_constructor() {
-    let font_data: Vec<u8> = std::fs::read("./res/Octoville.otf").unwrap();
-    let font: rusttype::Font<'static> = rusttype::Font::try_from_vec(font_data).unwrap();
+    let fonts = fonts();
     let a = SuperString::new(
         "ABCD".to_string(),
-        &font,
-        vec![ColorFmt::new(2, (1.0, 0.0, 0.0, 1.0))],
+        &fonts,
+        vec![StyleSpan::new(2..4, red())],
         1.0,
     );
 
     let b = SuperString {
         text: "ABCD".to_string(),
-        font: &font,
-        color_fmts: vec![ColorFmt {
-            glyph_index: 2,
-            color: (1.0, 0.0, 0.0, 1.0),
+        fonts: &fonts,
+        style_spans: vec![StyleSpan {
+            range: 2..4,
+            style: red(),
         }],
         normalized_height: 1.0,
+        layout: TextLayout::default(),
+        effects: TextEffects::default(),
     };
 
     assert_eq!(a.text, b.text);
-    assert_eq!(a.color_fmts, b.color_fmts);
+    assert_eq!(a.style_spans, b.style_spans);
     assert_eq!(a.normalized_height, b.normalized_height);
 }
 
 #[test]
 fn super_string_concatonation() {
-    let font_data: Vec<u8> = std::fs::read("./res/Octoville.otf").unwrap();
-    let font: rusttype::Font<'static> = rusttype::Font::try_from_vec(font_data).unwrap();
+    let fonts = fonts();
+    let cyan = Style {
+        color: (0.0, 1.0, 1.0, 1.0),
+        ..Style::default()
+    };
 
+    // spaces and characters the font doesn't have still take up an index
     let mut a = SuperString {
-        text: "ABCD".to_string(),
-        font: &font,
-        color_fmts: vec![ColorFmt {
-            glyph_index: 2,
-            color: (1.0, 0.0, 0.0, 1.0),
+        text: "A C\u{2603}".to_string(),
+        fonts: &fonts,
+        style_spans: vec![StyleSpan {
+            range: 2..3,
+            style: red(),
         }],
         normalized_height: 1.0,
+        layout: TextLayout::default(),
+        effects: TextEffects::default(),
     };
 
     let b = SuperString {
         text: "EFGH".to_string(),
-        font: &font,
-        color_fmts: vec![ColorFmt {
-            glyph_index: 2,
-            color: (0.0, 1.0, 1.0, 1.0),
+        fonts: &fonts,
+        style_spans: vec![StyleSpan {
+            range: 2..4,
+            style: cyan,
         }],
         normalized_height: 1.0,
+        layout: TextLayout::default(),
+        effects: TextEffects::default(),
     };
 
     a.cat(b);
-    assert_eq!("ABCDEFGH", a.text);
+    assert_eq!("A C\u{2603}EFGH", a.text);
     assert_eq!(
         vec![
-            ColorFmt {
-                glyph_index: 2,
-                color: (1.0, 0.0, 0.0, 1.0),
+            StyleSpan {
+                range: 2..3,
+                style: red(),
             },
-            ColorFmt {
-                glyph_index: 6,
-                color: (0.0, 1.0, 1.0, 1.0),
+            StyleSpan {
+                range: 6..8,
+                style: cyan,
             }
         ],
-        a.color_fmts
+        a.style_spans
     );
+    assert_eq!(cyan, a.style_at(6));
+    assert_eq!(Style::default(), a.style_at(5));
+}
+
+fn fonts() -> Vec<Font> {
+    vec![Font::load("./res/Octoville.otf").unwrap()]
+}
+
+// baseline of each glyph, in pixels from the top of the block
+fn baselines(block: &Block) -> Vec<f32> {
+    block.glyphs.iter().map(|(g, ..)| g.position().y).collect()
+}
+
+#[test]
+fn glyph_colors() {
+    let fonts = fonts();
+    let text = SuperString::new(
+        "AB\nCD".to_string(),
+        &fonts,
+        vec![StyleSpan::new(3..5, red())],
+        1.0,
+    );
+
+    let colors: Vec<_> = layout_block(&text, 16.0)
+        .glyphs
+        .into_iter()
+        .map(|(.., color)| color)
+        .collect();
+    assert_eq!(
+        vec![
+            (1.0, 1.0, 1.0, 1.0),
+            (1.0, 1.0, 1.0, 1.0),
+            red().color,
+            red().color
+        ],
+        colors,
+        "the newline still counts towards glyph indices"
+    );
+}
+
+#[test]
+fn newlines_and_line_spacing() {
+    let fonts = fonts();
+    let text = SuperString::new("AB\nCD\nE".to_string(), &fonts, Vec::new(), 0.1);
+    let block = layout_block(&text, 10.0);
+    let y = baselines(&block);
+    assert_eq!(5, y.len());
+    assert_eq!(y[0], y[1]);
+    assert!((y[2] - y[0] - 10.0).abs() < 1e-4);
+    assert!((y[4] - y[0] - 20.0).abs() < 1e-4);
+    assert!((block.height - 30.0).abs() < 1e-4);
+
+    let spaced = text.with_layout(TextLayout {
+        line_spacing: 1.5,
+        ..TextLayout::default()
+    });
+    let block = layout_block(&spaced, 10.0);
+    let y = baselines(&block);
+    assert!((y[4] - y[0] - 30.0).abs() < 1e-4);
+    assert!((block.height - 40.0).abs() < 1e-4);
+}
+
+#[test]
+fn wrapping() {
+    let fonts = fonts();
+    let text = SuperString::new("AAA BBB CCC".to_string(), &fonts, Vec::new(), 0.1);
+    let (one_line_width, one_line_height) = text.measure();
+    let word = SuperString::new("AAA BBB".to_string(), &fonts, Vec::new(), 0.1);
+
+    // room for two words but not three
+    let wrapped = text.with_layout(TextLayout {
+        max_width: Some(word.measure().0 + 0.001),
+        ..TextLayout::default()
+    });
+    let (width, height) = wrapped.measure();
+    assert!(width < one_line_width);
+    assert!((height - one_line_height * 2.0).abs() < 1e-4);
+    let block = layout_block(&wrapped, 0.1);
+    let y = baselines(&block);
+    assert_eq!(y[0], y[6], "AAA BBB on the first line");
+    assert!(y[8] > y[6], "CCC on the second");
+    assert!(block.glyphs[8].0.position().x.abs() < 1e-6);
+
+    // a word that doesn't fit anywhere gets broken up
+    let narrow =
+        SuperString::new("AAAA".to_string(), &fonts, Vec::new(), 0.1).with_layout(TextLayout {
+            max_width: Some(0.0),
+            ..TextLayout::default()
+        });
+    assert!((narrow.measure().1 - 0.4).abs() < 1e-4);
+}
+
+#[test]
+fn alignment() {
+    let fonts = fonts();
+    let lines = |align| {
+        let text = SuperString::new("AAAA\nA".to_string(), &fonts, Vec::new(), 0.1).with_layout(
+            TextLayout {
+                align,
+                ..TextLayout::default()
+            },
+        );
+        let block = layout_block(&text, 10.0);
+        (block.width, block.glyphs[4].0.position().x)
+    };
+
+    let (width, left) = lines(Align::Left);
+    assert_eq!(0.0, left);
+    let (_, right) = lines(Align::Right);
+    let (_, center) = lines(Align::Center);
+    assert!(right > center && center > left);
+    assert!((center - right / 2.0).abs() < 1e-4);
+    assert!(right < width);
+}
+
+#[test]
+fn scaled_spans() {
+    let fonts = fonts();
+    let text = SuperString::new(
+        "A\nA".to_string(),
+        &fonts,
+        vec![StyleSpan::new(
+            2..3,
+            Style {
+                scale: 2.0,
+                ..Style::default()
+            },
+        )],
+        0.1,
+    );
+    let block = layout_block(&text, 10.0);
+    assert_eq!(10.0, block.glyphs[0].0.scale().y);
+    assert_eq!(20.0, block.glyphs[1].0.scale().y);
+    assert!(
+        (block.height - 30.0).abs() < 1e-4,
+        "the second line is twice as tall"
+    );
+}
+
+#[test]
+fn decorations() {
+    let fonts = fonts();
+    let spans = markup::parse("[u]AB[/u] [s]C[/s]").unwrap();
+    let mut text = SuperString::new(String::new(), &fonts, Vec::new(), 0.1);
+    for span in spans {
+        let length = span.text.chars().count();
+        text.cat(SuperString::new(
+            span.text,
+            &fonts,
+            vec![StyleSpan::new(0..length, span.style)],
+            0.1,
+        ));
+    }
+
+    let block = layout_block(&text, 16.0);
+    assert_eq!(2, block.strokes.len(), "AB's underline is a single stroke");
+    let (underline, strikethrough) = (block.strokes[0], block.strokes[1]);
+    let baseline = block.glyphs[0].0.position().y;
+    assert!(underline.top > baseline);
+    assert!(strikethrough.bottom < baseline);
+    assert_eq!(block.glyphs[2].0.position().x, underline.right);
+
+    // and they show up when rasterized
+    let (pixels, width, _) = rasterize(&text, 16.0);
+    let row = underline.top.round() as usize;
+    assert_eq!(1.0, pixels[(1 + row * width) * 4 + 3]);
+}
+
+#[test]
+fn effect_layers() {
+    use super::super::draw_list::{Glow, Outline, Shadow};
+
+    let fonts = fonts();
+    let plain = SuperString::new("A".to_string(), &fonts, Vec::new(), 0.1);
+    assert_eq!(0, margin(&plain, 20.0));
+    assert_eq!(1, layers(&plain, 20.0).len());
+
+    let black = (0.0, 0.0, 0.0, 1.0);
+    let text = plain.with_effects(TextEffects {
+        outline: Some(Outline {
+            width: 0.1,
+            color: black,
+        }),
+        shadow: Some(Shadow {
+            offset: (0.2, 0.3),
+            color: black,
+        }),
+        glow: Some(Glow {
+            radius: 0.5,
+            color: (1.0, 1.0, 0.0, 1.0),
+        }),
+    });
+    let layers = layers(&text, 20.0);
+    let last = layers.last().unwrap();
+    assert_eq!((0.0, 0.0), last.offset);
+    assert!(last.color.is_none(), "the text itself goes in front");
+    let shadow = layers.iter().position(|l| l.offset == (4.0, 6.0)).unwrap();
+    assert!(
+        layers[..shadow]
+            .iter()
+            .all(|l| l.color.unwrap().2 == 0.0 && l.color.unwrap().3 < 1.0),
+        "glow behind the shadow"
+    );
+    assert!(
+        layers[shadow + 1..layers.len() - 1]
+            .iter()
+            .all(|l| l.color == Some(black)),
+        "outline in front of it"
+    );
+    assert_eq!(10, margin(&text, 20.0), "as far as the glow reaches");
+
+    // a fading glyph fades its outline too
+    assert_eq!(
+        (0.0, 0.0, 0.0, 0.5),
+        layers[shadow].color((1.0, 1.0, 1.0, 0.5))
+    );
+}
+
+#[test]
+fn outline_rasterized() {
+    use super::super::draw_list::Outline;
+
+    let fonts = fonts();
+    let plain = SuperString::new("I".to_string(), &fonts, Vec::new(), 0.1);
+    let outlined =
+        SuperString::new("I".to_string(), &fonts, Vec::new(), 0.1).with_effects(TextEffects {
+            outline: Some(Outline {
+                width: 0.1,
+                color: (1.0, 0.0, 0.0, 1.0),
+            }),
+            ..TextEffects::default()
+        });
+    let (plain_pixels, plain_width, plain_height) = rasterize(&plain, 20.0);
+    let (pixels, width, height) = rasterize(&outlined, 20.0);
+    let margin = margin(&outlined, 20.0);
+    assert_eq!(2, margin);
+    assert_eq!((plain_width + 4, plain_height + 4), (width, height));
+
+    let covered = |pixels: &[f32]| pixels.chunks(4).filter(|p| p[3] > 0.5).count();
+    assert!(covered(&pixels) > covered(&plain_pixels));
+    // the glyph's own pixels stay white, with red around them
+    let reds = pixels
+        .chunks(4)
+        .filter(|p| p[3] > 0.5 && p[0] > 0.9 && p[1] < 0.1)
+        .count();
+    let whites = pixels
+        .chunks(4)
+        .filter(|p| p[3] > 0.5 && p[1] > 0.9)
+        .count();
+    assert!(reds > 0 && whites > 0);
+}
+
+#[test]
+fn font_fallback() {
+    let fonts = fonts();
+    assert_eq!(Some(0), font_for(&fonts, 'A'));
+    assert_eq!(None, font_for(&fonts, '\u{2603}'));
+    assert_eq!(None, font_for(&[], 'A'));
+
+    // the first font that has a character wins
+    let twice = vec![fonts[0].clone(), fonts[0].clone()];
+    assert_eq!(Some(0), font_for(&twice, 'A'));
+
+    let text = SuperString::new(
+        "A\u{2603}B\n\u{2603}\u{2764}".to_string(),
+        &fonts,
+        Vec::new(),
+        0.1,
+    );
+    assert_eq!(vec!['\u{2603}', '\u{2764}'], text.missing_characters());
+    assert!(
+        SuperString::new("AB\nC".to_string(), &fonts, Vec::new(), 0.1)
+            .missing_characters()
+            .is_empty()
+    );
+
+    // missing characters still take up space, as the first font's missing glyph
+    let block = layout_block(&text, 10.0);
+    assert_eq!(5, block.glyphs.len());
+    assert_eq!(rusttype::GlyphId(0), block.glyphs[1].0.id());
+    assert!(block.glyphs.iter().all(|(_, font, _)| *font == 0));
+}
+
+// an 'A' and a snowman on a plain white 5x4 page, at 4 font pixels to a line
+fn bitmap_font() -> Font {
+    let descriptor = "common lineHeight=4 base=3\n\
+        page id=0 file=page.png\n\
+        char id=65 x=0 y=0 width=2 height=3 xoffset=0 yoffset=0 xadvance=3 page=0\n\
+        char id=9731 x=2 y=0 width=3 height=4 xoffset=1 yoffset=-1 xadvance=4 page=0\n\
+        kerning first=65 second=9731 amount=-1";
+    let page = |_: &str| -> Result<Image, Box<dyn Error>> {
+        Ok(Image {
+            width: 5,
+            height: 4,
+            data: vec![255; 5 * 4 * 4],
+        })
+    };
+    Font::from(BitmapFont::parse(descriptor, page).unwrap())
+}
+
+#[test]
+fn bitmap_font_layout() {
+    let fonts = vec![bitmap_font()];
+    let text = SuperString::new(
+        "A\u{2603}".to_string(),
+        &fonts,
+        vec![StyleSpan::new(1..2, red())],
+        0.1,
+    );
+
+    // twice the font's line height, so two pixels per font pixel
+    let block = layout_block(&text, 8.0);
+    assert!(block.glyphs.is_empty());
+    let (a, snowman) = (block.bitmap_glyphs[0], block.bitmap_glyphs[1]);
+    assert_eq!((0.0, 0.0, 2.0), (a.left, a.top, a.scale));
+    assert_eq!(
+        (6.0 - 2.0 + 2.0, -2.0),
+        (snowman.left, snowman.top),
+        "after A's advance, kerned, then offset"
+    );
+    assert_eq!(red().color, snowman.color);
+    assert_eq!(12.0, block.width);
+
+    let (pixels, width, _) = rasterize(&text, 8.0);
+    let pixel = |x: usize, y: usize| &pixels[(x + y * width) * 4..(x + y * width) * 4 + 4];
+    assert_eq!(&[1.0, 1.0, 1.0, 1.0], pixel(3, 5), "inside A");
+    assert_eq!(0.0, pixel(4, 0)[3], "between them");
+    assert_eq!(&[1.0, 0.0, 0.0, 1.0], pixel(7, 0), "tinted like a sprite");
+}
+
+#[test]
+fn mixed_fonts() {
+    let fonts = vec![fonts().remove(0), bitmap_font()];
+    let text = SuperString::new("A\u{2603}".to_string(), &fonts, Vec::new(), 0.1);
+    assert!(text.missing_characters().is_empty());
+
+    let block = layout_block(&text, 8.0);
+    assert_eq!(1, block.glyphs.len(), "the outline font has A");
+    assert_eq!(1, block.bitmap_glyphs.len(), "but not the snowman");
+    assert_eq!(1, block.bitmap_glyphs[0].font);
+    let advance = block.glyphs[0].0.unpositioned().h_metrics().advance_width;
+    assert!(
+        (block.bitmap_glyphs[0].left - 2.0 - advance).abs() < 1e-4,
+        "no kerning between fonts"
+    );
+}
+
+#[test]
+fn inline_icons() {
+    let fonts = fonts();
+    let icon = Icon {
+        texture_region: ((0.5, 0.25), (0.75, 0.5)),
+        aspect_ratio: 2.0,
+    };
+    let mut text = SuperString::new("A".to_string(), &fonts, Vec::new(), 0.1);
+    text.cat(SuperString::new(
+        format!("{}B", ICON_CHARACTER),
+        &fonts,
+        vec![StyleSpan::new(
+            0..1,
+            Style {
+                icon: Some(icon),
+                ..Style::default()
+            },
+        )],
+        0.1,
+    ));
+    assert!(text.missing_characters().is_empty());
+
+    let block = layout_block(&text, 20.0);
+    assert_eq!(2, block.glyphs.len(), "A and B, the icon isn't a glyph");
+    let placed = block.icons[0];
+    let baseline = block.glyphs[0].0.position().y;
+    let ascent = fonts[0].ascent(20.0);
+    assert_eq!(icon.texture_region, placed.texture_region);
+    assert!(
+        (placed.top + placed.height - baseline).abs() < 1e-4,
+        "sitting on the baseline"
+    );
+    assert!((placed.height - ascent).abs() < 1e-4, "as tall as the text");
+    assert!((placed.width - ascent * 2.0).abs() < 1e-4);
+    assert_eq!(
+        placed.left + placed.width,
+        block.glyphs[1].0.position().x,
+        "B comes right after it"
+    );
+    assert!(
+        (text.measure().0 * 200.0 - block.width).abs() < 1e-3,
+        "measured along with the glyphs"
+    );
+
+    // icons go on top of the rasterized text, in playfield coordinates
+    let icons = positioned_icons(&text, 20.0, (-1.0, 1.0), 0.005);
+    assert_eq!(1, icons.len());
+    assert!((icons[0].left - (-1.0 + placed.left * 0.005)).abs() < 1e-6);
+    assert!((icons[0].top - (1.0 - placed.top * 0.005)).abs() < 1e-6);
 }
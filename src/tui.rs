@@ -0,0 +1,313 @@
+use super::logic;
+use super::GameState;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, event, execute, queue, terminal};
+use glium::glutin::event::VirtualKeyCode;
+
+#[cfg(test)]
+mod tests;
+
+pub const FRAME_TIME: Duration = Duration::from_millis(33); // how often the terminal gets redrawn
+pub const KEY_HOLD_TIME: Duration = Duration::from_millis(150); // most terminals never report key releases, so presses are held for this long
+const HUD_ROWS: u16 = 1;
+
+const BACKGROUND_COLOR: Color = Color::Rgb { r: 0, g: 0, b: 0 };
+const PLAYFIELD_COLOR: Color = Color::Rgb {
+    r: 0,
+    g: 160,
+    b: 80,
+};
+const BIRDY_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 230,
+    b: 0,
+};
+const ROCK_COLOR: Color = Color::Rgb {
+    r: 128,
+    g: 128,
+    b: 128,
+};
+const COIN_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 170,
+    b: 0,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Style {
+    HalfBlocks, // two pixels per character cell using '▀', needs unicode and truecolor
+    Ascii, // one pixel per character cell, a letter for each kind of thing, in named ANSI colors any terminal has
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Pixel {
+    Background, // letterbox, outside of the playfield
+    Playfield,
+    Birdy,
+    Rock,
+    Coin,
+}
+impl Pixel {
+    fn color(self) -> Color {
+        match self {
+            Pixel::Background => BACKGROUND_COLOR,
+            Pixel::Playfield => PLAYFIELD_COLOR,
+            Pixel::Birdy => BIRDY_COLOR,
+            Pixel::Rock => ROCK_COLOR,
+            Pixel::Coin => COIN_COLOR,
+        }
+    }
+
+    // the nearest of the 16 named colors, which terminals without truecolor still understand
+    fn ansi_color(self) -> Color {
+        match self {
+            Pixel::Background => Color::Reset,
+            Pixel::Playfield => Color::DarkGreen,
+            Pixel::Birdy => Color::Yellow,
+            Pixel::Rock => Color::Grey,
+            Pixel::Coin => Color::DarkYellow,
+        }
+    }
+
+    fn character(self) -> char {
+        match self {
+            Pixel::Background => ' ',
+            Pixel::Playfield => '.',
+            Pixel::Birdy => '@',
+            Pixel::Rock => '#',
+            Pixel::Coin => 'o',
+        }
+    }
+}
+
+// picture of the game, row-major, with the playfield as a square in the middle
+fn rasterize(
+    game_state: &GameState,
+    width: usize,
+    height: usize,
+    pixel_aspect_ratio: f32,
+) -> Vec<Pixel> {
+    let mut pixels = vec![Pixel::Background; width * height];
+
+    // size of the playfield in pixels, across and down
+    let side = f32::min(width as f32 * pixel_aspect_ratio, height as f32);
+    let (playfield_width, playfield_height) = (side / pixel_aspect_ratio, side);
+    let left = (width as f32 - playfield_width) / 2.0;
+    let top = (height as f32 - playfield_height) / 2.0;
+    let to_column =
+        |x: f32| (left + (x + 1.0) / 2.0 * playfield_width).clamp(0.0, width as f32) as usize;
+    let to_row =
+        |y: f32| (top + (1.0 - y) / 2.0 * playfield_height).clamp(0.0, height as f32) as usize;
+
+    let mut fill = |(x_min, x_max, y_min, y_max): (f32, f32, f32, f32), pixel: Pixel| {
+        // always cover at least one pixel, so tiny things don't vanish at low resolutions
+        let (row_start, column_start) = (to_row(y_max), to_column(x_min));
+        let row_end = usize::max(to_row(y_min), row_start + 1).min(height);
+        let column_end = usize::max(to_column(x_max), column_start + 1).min(width);
+        for row in row_start..row_end {
+            for column in column_start..column_end {
+                pixels[column + row * width] = pixel;
+            }
+        }
+    };
+    let bounds = |obj: logic::PhysObj| {
+        (
+            f32::max(obj.x - obj.width, -1.0),
+            f32::min(obj.x + obj.width, 1.0),
+            f32::max(obj.y - obj.height, -1.0),
+            f32::min(obj.y + obj.height, 1.0),
+        )
+    };
+
+    fill((-1.0, 1.0, -1.0, 1.0), Pixel::Playfield);
+    for coin in game_state
+        .coins
+        .iter()
+        .filter(|obj| obj.y.abs() < 1.0 + obj.height)
+    {
+        fill(bounds(*coin), Pixel::Coin);
+    }
+    for rock in game_state
+        .rocks
+        .iter()
+        .filter(|obj| obj.y.abs() < 1.0 + obj.height)
+    {
+        fill(bounds(*rock), Pixel::Rock);
+    }
+    fill(bounds(game_state.birdy), Pixel::Birdy);
+
+    pixels
+}
+
+fn hud(game_state: &GameState) -> String {
+    let mut hud = format!("Score: {}", game_state.score);
+    if game_state.combo > 0 {
+        hud += &format!(
+            "  Combo: {} x{}",
+            game_state.combo,
+            logic::combo::multiplier(game_state.combo)
+        );
+    }
+    hud + "  [Space] flap  [Left/Right] move  [Q] quit"
+}
+
+// queue up a whole frame, only changing colors when we have to
+fn draw(
+    out: &mut impl Write,
+    game_state: &GameState,
+    style: Style,
+    columns: u16,
+    rows: u16,
+) -> std::io::Result<()> {
+    let width = columns as usize;
+    let height = rows.saturating_sub(HUD_ROWS) as usize;
+
+    queue!(
+        out,
+        cursor::MoveTo(0, 0),
+        SetBackgroundColor(Color::Reset),
+        SetForegroundColor(Color::Reset),
+        terminal::Clear(terminal::ClearType::CurrentLine),
+        Print(hud(game_state).chars().take(width).collect::<String>()),
+    )?;
+
+    let mut foreground = Color::Reset;
+    let mut background = Color::Reset;
+    match style {
+        Style::HalfBlocks => {
+            // character cells are about twice as tall as they are wide, so half a cell is square
+            let pixels = rasterize(game_state, width, height * 2, 1.0);
+            for row in 0..height {
+                queue!(out, cursor::MoveTo(0, row as u16 + HUD_ROWS))?;
+                for column in 0..width {
+                    let top = pixels[column + row * 2 * width].color();
+                    let bottom = pixels[column + (row * 2 + 1) * width].color();
+                    if top != foreground {
+                        foreground = top;
+                        queue!(out, SetForegroundColor(foreground))?;
+                    }
+                    if bottom != background {
+                        background = bottom;
+                        queue!(out, SetBackgroundColor(background))?;
+                    }
+                    queue!(out, Print('▀'))?;
+                }
+            }
+        }
+        Style::Ascii => {
+            let pixels = rasterize(game_state, width, height, 0.5);
+            for row in 0..height {
+                queue!(out, cursor::MoveTo(0, row as u16 + HUD_ROWS))?;
+                for pixel in pixels[row * width..(row + 1) * width].iter() {
+                    if pixel.ansi_color() != foreground {
+                        foreground = pixel.ansi_color();
+                        queue!(out, SetForegroundColor(foreground))?;
+                    }
+                    queue!(out, Print(pixel.character()))?;
+                }
+            }
+        }
+    }
+    queue!(
+        out,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset)
+    )?;
+    out.flush()
+}
+
+fn virtual_keycode(code: KeyCode) -> Option<VirtualKeyCode> {
+    match code {
+        KeyCode::Char(' ') | KeyCode::Up => Some(VirtualKeyCode::Space),
+        KeyCode::Left => Some(VirtualKeyCode::Left),
+        KeyCode::Right => Some(VirtualKeyCode::Right),
+        _ => None,
+    }
+}
+
+// puts the terminal back the way we found it, even if we bail out early
+struct TerminalGuard {
+    enhanced_keyboard: bool,
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let mut out = std::io::stdout();
+        if self.enhanced_keyboard {
+            let _ = execute!(out, event::PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// play in the terminal until the player quits
+pub fn run(style: Style) -> std::io::Result<()> {
+    let mut out = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    let enhanced_keyboard = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    let _guard = TerminalGuard { enhanced_keyboard };
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    if enhanced_keyboard {
+        // lets us see key releases, instead of guessing with KEY_HOLD_TIME
+        execute!(
+            out,
+            event::PushKeyboardEnhancementFlags(
+                event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            )
+        )?;
+    }
+
+    let mut game_state = GameState::new();
+    let mut held_keys: HashMap<VirtualKeyCode, Instant> = HashMap::new();
+    let mut then = Instant::now();
+
+    loop {
+        // handle input until it's time for the next frame
+        let frame_end = then + FRAME_TIME;
+        while let Some(timeout) = frame_end.checked_duration_since(Instant::now()) {
+            if !event::poll(timeout)? {
+                break;
+            }
+            if let Event::Key(key) = event::read()? {
+                let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                    || (key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL));
+                if quit {
+                    return Ok(());
+                }
+                if let Some(keycode) = virtual_keycode(key.code) {
+                    match key.kind {
+                        KeyEventKind::Release => {
+                            held_keys.remove(&keycode);
+                        }
+                        _ => {
+                            held_keys.insert(keycode, Instant::now());
+                        }
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if !enhanced_keyboard {
+            held_keys.retain(|_, &mut time| now.duration_since(time) < KEY_HOLD_TIME);
+        }
+        game_state.keys = held_keys.keys().copied().collect();
+
+        logic::tick(&mut game_state, now, now.duration_since(then).as_secs_f32());
+        if game_state.dead {
+            game_state = GameState::new();
+        }
+
+        let (columns, rows) = terminal::size()?;
+        draw(&mut out, &game_state, style, columns, rows)?;
+
+        then = now;
+    }
+}
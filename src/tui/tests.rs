@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn playfield_is_letterboxed() {
+    let game_state = GameState::new();
+    let pixels = rasterize(&game_state, 8, 4, 1.0);
+
+    assert_eq!(Pixel::Background, pixels[0]);
+    assert_eq!(Pixel::Background, pixels[7]);
+    assert_eq!(Pixel::Playfield, pixels[2]);
+    assert_eq!(Pixel::Playfield, pixels[5 + 3 * 8]);
+}
+
+#[test]
+fn everything_shows_up() {
+    let mut game_state = GameState::new();
+    game_state.rocks.push(logic::PhysObj {
+        x: 0.5,
+        y: 0.5,
+        x_velocity: 0.0,
+        y_velocity: -0.3,
+        width: 0.1,
+        height: 0.1,
+    });
+    game_state.coins.push(logic::PhysObj {
+        x: -0.5,
+        y: -0.5,
+        x_velocity: 0.0,
+        y_velocity: 0.3,
+        width: 0.01, // smaller than a pixel
+        height: 0.01,
+    });
+    let pixels = rasterize(&game_state, 20, 20, 1.0);
+
+    assert_eq!(Pixel::Birdy, pixels[9 + 9 * 20]);
+    assert_eq!(Pixel::Rock, pixels[15 + 4 * 20]);
+    assert_eq!(Pixel::Coin, pixels[4 + 14 * 20]);
+}
+
+#[test]
+fn draws_a_frame() {
+    let game_state = GameState::new();
+    let mut out = Vec::new();
+    draw(&mut out, &game_state, Style::Ascii, 20, 11).unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Score: 0"));
+    assert!(out.contains('@'));
+    // no truecolor escapes, just the named colors
+    assert!(!out.contains("38;2;"));
+}